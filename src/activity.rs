@@ -0,0 +1,160 @@
+//! When a project was last worked on.
+//!
+//! An artifact's own modification time only changes when entries directly
+//! inside it are added or removed, so a project edited every day can look
+//! months old. These signals look at the project instead.
+
+use crate::git;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+/// Lockfiles of the package managers whose artifacts are detected
+const LOCKFILES: &[&str] = &[
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lock",
+    "bun.lockb",
+    "Cargo.lock",
+    "poetry.lock",
+    "Pipfile.lock",
+    "uv.lock",
+    "gradle.lockfile",
+];
+
+/// What a project's age is measured from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivitySignal {
+    /// Modification time of the artifact directory itself
+    Artifact,
+    /// Newest file in the project outside artifacts and hidden directories
+    Sources,
+    /// Newest commit in the project's git repository
+    GitCommit,
+    /// Newest lockfile next to the artifact
+    Lockfile,
+    /// Whichever of the above is most recent
+    #[default]
+    Newest,
+}
+
+impl ActivitySignal {
+    pub const ALL: [ActivitySignal; 5] = [
+        ActivitySignal::Newest,
+        ActivitySignal::Sources,
+        ActivitySignal::GitCommit,
+        ActivitySignal::Lockfile,
+        ActivitySignal::Artifact,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ActivitySignal::Artifact => "ARTIFACT",
+            ActivitySignal::Sources => "SOURCES",
+            ActivitySignal::GitCommit => "GIT COMMIT",
+            ActivitySignal::Lockfile => "LOCKFILE",
+            ActivitySignal::Newest => "NEWEST",
+        }
+    }
+
+    /// The signal after this one, for cycling through them in the UI
+    pub fn next(&self) -> Self {
+        let position = Self::ALL.iter().position(|s| s == self).unwrap_or(0);
+        Self::ALL[(position + 1) % Self::ALL.len()]
+    }
+}
+
+impl FromStr for ActivitySignal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "artifact" => Ok(ActivitySignal::Artifact),
+            "sources" => Ok(ActivitySignal::Sources),
+            "git" | "git_commit" => Ok(ActivitySignal::GitCommit),
+            "lockfile" => Ok(ActivitySignal::Lockfile),
+            "newest" => Ok(ActivitySignal::Newest),
+            other => Err(format!("unknown activity signal '{}'", other)),
+        }
+    }
+}
+
+impl fmt::Display for ActivitySignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ActivitySignal::Artifact => "artifact",
+            ActivitySignal::Sources => "sources",
+            ActivitySignal::GitCommit => "git_commit",
+            ActivitySignal::Lockfile => "lockfile",
+            ActivitySignal::Newest => "newest",
+        })
+    }
+}
+
+/// When the project at `project` was last active according to `signal`, and
+/// the signal that time came from. Falls back to `artifact_modified` when the
+/// signal has nothing to go on, e.g. a project outside any git repository.
+///
+/// `newest_source` finds the newest source file in a project. The scanner
+/// walks it with the same exclusions and limits as the scan itself.
+pub fn last_active(
+    signal: ActivitySignal,
+    project: &Path,
+    artifact_modified: SystemTime,
+    newest_source: &dyn Fn(&Path) -> Option<SystemTime>,
+) -> (SystemTime, ActivitySignal) {
+    let measured = match signal {
+        ActivitySignal::Artifact => None,
+        ActivitySignal::Sources => newest_source(project),
+        ActivitySignal::GitCommit => {
+            git::find_repo(project).and_then(|repo| git::last_commit_time(&repo.git_dir))
+        }
+        ActivitySignal::Lockfile => newest_lockfile(project),
+        ActivitySignal::Newest => {
+            // Checked one by one so the card can say which signal won
+            return [
+                ActivitySignal::Sources,
+                ActivitySignal::GitCommit,
+                ActivitySignal::Lockfile,
+            ]
+            .into_iter()
+            .map(|signal| last_active(signal, project, artifact_modified, newest_source))
+            .fold(
+                (artifact_modified, ActivitySignal::Artifact),
+                |newest, candidate| {
+                    if candidate.0 > newest.0 {
+                        candidate
+                    } else {
+                        newest
+                    }
+                },
+            );
+        }
+    };
+
+    match measured {
+        Some(time) => (time, signal),
+        None => (artifact_modified, ActivitySignal::Artifact),
+    }
+}
+
+/// Whether a lockfile sits in `project`
+pub fn has_lockfile(project: &Path) -> bool {
+    LOCKFILES.iter().any(|name| project.join(name).is_file())
+}
+
+fn newest_lockfile(project: &Path) -> Option<SystemTime> {
+    LOCKFILES
+        .iter()
+        .filter_map(|name| {
+            fs::metadata(project.join(name))
+                .and_then(|m| m.modified())
+                .ok()
+        })
+        .max()
+}
@@ -1,38 +1,247 @@
+use crate::activity::ActivitySignal;
+use crate::cancel::CancelToken;
+use crate::cleaner::{self, CleanSummary, DeleteMode};
 use crate::config::Config;
+use crate::errors::ScanError;
+use crate::filters;
+use crate::index::ScanIndex;
+use crate::listing;
+use crate::mounts::SkippedMount;
+use crate::patterns::{self, PathFilter};
+use crate::preview::{self, DryRunReport};
+use crate::protect::{self, ProtectList};
+use crate::quarantine::{Quarantine, QuarantineEntry};
+use crate::report::{self, ReportFormat};
+use crate::rules::{RuleAction, RuleSet};
 use crate::scanner::{ProjectInfo, Scanner};
-use std::fs;
+use crate::trash;
+use crate::watcher::{self, Update, WatchEvent, Watcher};
+use gpui::{Context, FocusHandle, KeyDownEvent};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, TryRecvError};
+use std::time::Duration;
+
+const SCAN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const WATCH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A limit in the header that can be typed into
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterField {
+    MinAge,
+    MaxAge,
+    MinSize,
+}
+
+/// Sent from the background scan to the UI thread
+enum ScanMessage {
+    Progress { folder: String, progress: f32 },
+    Found(Box<ProjectInfo>),
+    Skipped(Vec<SkippedMount>),
+    Errors(Vec<ScanError>),
+    Done(Vec<ProjectInfo>),
+}
+
+/// The scanner settings from the config, taken along to background tasks so
+/// every scanner the app builds walks the same way
+#[derive(Clone)]
+struct ScanSettings {
+    threads: usize,
+    filter: PathFilter,
+    one_filesystem: bool,
+    network_mounts: bool,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    activity_signal: ActivitySignal,
+}
+
+impl ScanSettings {
+    fn new(config: &Config, filter: PathFilter) -> Self {
+        Self {
+            threads: config.scan_threads,
+            filter,
+            one_filesystem: config.one_filesystem,
+            network_mounts: config.scan_network_mounts,
+            max_depth: config.max_depth,
+            follow_symlinks: config.follow_symlinks,
+            activity_signal: config.activity_signal,
+        }
+    }
+
+    /// A scanner reporting every artifact regardless of age
+    fn scanner(&self) -> Scanner {
+        Scanner::new(0)
+            .with_threads(self.threads)
+            .with_filter(self.filter.clone())
+            .with_one_filesystem(self.one_filesystem)
+            .with_network_mounts(self.network_mounts)
+            .with_max_depth(self.max_depth)
+            .with_follow_symlinks(self.follow_symlinks)
+            .with_activity_signal(self.activity_signal)
+    }
+}
 
 pub struct StorageCleaner {
     pub projects: Vec<ProjectInfo>,
     pub all_projects: Vec<ProjectInfo>, // Cache of all scanned projects
     pub config: Config,
     pub is_scanning: bool,
+    pub is_deleting: bool,
+    /// A dry run of the selection is walking its artifacts
+    pub is_previewing: bool,
+    /// Cancels the running scan or deletion, if any
+    pub cancel_token: Option<CancelToken>,
+    /// Stops the live watcher started after the last scan, if any
+    pub watch_token: Option<CancelToken>,
     pub status_message: String,
     pub scan_progress: f32,
     pub current_scan_folder: String,
+    /// Mounts the last scan stayed out of
+    pub skipped_mounts: Vec<SkippedMount>,
+    /// What the last scan could not read
+    pub scan_errors: Vec<ScanError>,
+    pub show_errors: bool,
+    /// The delete button was clicked once with projects selected whose
+    /// repositories have local work, and the next click goes ahead
+    pub confirm_local_work: bool,
+    /// Compiled from `config.protected`
+    pub protect: ProtectList,
+    /// Compiled from `config.rules`
+    pub rules: RuleSet,
     pub threshold_enabled: bool,
+    pub show_quarantine: bool,
+    pub quarantine: Vec<QuarantineEntry>,
+    pub preview: Option<DryRunReport>,
+    pub show_patterns: bool,
+    /// Pattern being typed into the exclusion pattern editor
+    pub pattern_input: String,
+    pub pattern_focus: FocusHandle,
+    /// Limit being typed into, if any
+    pub filter_edit: Option<FilterField>,
+    pub filter_input: String,
+    pub filter_focus: FocusHandle,
+    /// Groups folded away in the project list, by group path
+    pub collapsed_groups: HashSet<PathBuf>,
 }
 
 impl StorageCleaner {
-    pub fn new() -> Self {
+    pub fn new(cx: &mut Context<Self>) -> Self {
         let config = Config::load();
-        let status_message = format!("Ready. Scan directory: {}", config.scan_path.display());
+        let mut status_message =
+            format!("Ready. Scan roots: {}", roots_label(&config.scan_roots()));
+
+        // Expired quarantine entries are purged on every start
+        let mut quarantine = Quarantine::open();
+        let (purged, purged_bytes) = quarantine.purge_older_than(config.quarantine_days);
+        if purged > 0 {
+            status_message = format!(
+                "{} - Purged {} expired quarantine item(s), {:.2} GB",
+                status_message,
+                purged,
+                purged_bytes as f64 / (1024.0 * 1024.0 * 1024.0)
+            );
+        }
+
+        let protect = ProtectList::new(&config.protected);
+        if !protect.invalid().is_empty() {
+            status_message = format!(
+                "{} - ❌ {} in the protected list, removing is blocked until it is fixed",
+                status_message,
+                protect.invalid().join("; ")
+            );
+        }
+        let rules = match RuleSet::new(&config.rules) {
+            Ok(rules) => rules,
+            Err(e) => {
+                status_message = format!("{} - ❌ {}, rules are off", status_message, e);
+                RuleSet::default()
+            }
+        };
 
         Self {
             projects: Vec::new(),
             all_projects: Vec::new(),
             config,
             is_scanning: false,
+            is_deleting: false,
+            is_previewing: false,
+            cancel_token: None,
+            watch_token: None,
             status_message,
             scan_progress: 0.0,
             current_scan_folder: String::new(),
+            skipped_mounts: Vec::new(),
+            scan_errors: Vec::new(),
+            show_errors: false,
+            confirm_local_work: false,
+            protect,
+            rules,
             threshold_enabled: true,
+            show_quarantine: false,
+            quarantine: quarantine.entries().to_vec(),
+            preview: None,
+            show_patterns: false,
+            pattern_input: String::new(),
+            pattern_focus: cx.focus_handle(),
+            filter_edit: None,
+            filter_input: String::new(),
+            filter_focus: cx.focus_handle(),
+            collapsed_groups: HashSet::new(),
         }
     }
 
-    pub fn set_scan_path(&mut self, path: std::path::PathBuf) {
-        self.config.scan_path = path;
-        self.status_message = format!("Scan directory set to: {}", self.config.scan_path.display());
+    pub fn add_root(&mut self, path: PathBuf, cx: &mut Context<Self>) {
+        if self.is_scanning || self.is_deleting {
+            return;
+        }
+        if !self.config.add_root(path.clone()) {
+            self.status_message = format!("Already a scan root: {}", path.display());
+            return;
+        }
+        self.roots_changed(cx);
+        self.status_message = format!("Added scan root: {}", path.display());
+    }
+
+    pub fn remove_root(&mut self, index: usize, cx: &mut Context<Self>) {
+        if self.is_scanning || self.is_deleting || index >= self.config.roots.len() {
+            return;
+        }
+        let root = self.config.roots.remove(index);
+        self.roots_changed(cx);
+        self.status_message = format!("Removed scan root: {}", root.path.display());
+    }
+
+    pub fn toggle_root(&mut self, index: usize, cx: &mut Context<Self>) {
+        if self.is_scanning || self.is_deleting {
+            return;
+        }
+        let Some(root) = self.config.roots.get_mut(index) else {
+            return;
+        };
+        root.enabled = !root.enabled;
+        let message = format!(
+            "{} scan root: {}",
+            if root.enabled { "Enabled" } else { "Disabled" },
+            root.path.display()
+        );
+        self.roots_changed(cx);
+        self.status_message = message;
+    }
+
+    /// Drop results outside the enabled roots, follow the new roots and save
+    fn roots_changed(&mut self, cx: &mut Context<Self>) {
+        let config = &self.config;
+        self.all_projects
+            .retain(|p| config.root_of(&p.artifact_path).is_some());
+        self.apply_filter();
+
+        if self.watch_token.is_some() {
+            self.stop_watching();
+            self.start_watching(cx);
+        }
 
         // Save config
         if let Err(e) = self.config.save() {
@@ -40,98 +249,764 @@ impl StorageCleaner {
         }
     }
 
-    pub fn scan_for_projects(&mut self) {
-        if self.is_scanning {
+    pub fn scan_for_projects(&mut self, cx: &mut Context<Self>) {
+        if self.is_scanning || self.is_deleting {
             return; // Prevent multiple scans
         }
 
+        let scan_roots = self.config.scan_roots();
+        if scan_roots.is_empty() {
+            self.status_message = "No scan roots enabled - add or enable one first".to_string();
+            return;
+        }
+        let filter = match self.config.path_filter() {
+            Ok(filter) => filter,
+            Err(e) => {
+                self.status_message = format!("❌ Not scanning: {}", e);
+                return;
+            }
+        };
+
+        self.stop_watching();
         self.is_scanning = true;
-        self.projects.clear();
-        self.all_projects.clear();
+        let cancel_token = CancelToken::new();
+        self.cancel_token = Some(cancel_token.clone());
+        self.preview = None;
         self.scan_progress = 0.0;
         self.current_scan_folder = String::new();
-        self.status_message = format!("Scanning {} ...", self.config.scan_path.display());
+        self.skipped_mounts.clear();
+        self.scan_errors.clear();
+        self.show_errors = false;
+
+        // Show what the last scan found right away; the rescan below verifies
+        // it and only re-sizes artifacts that changed since
+        let index = ScanIndex::load();
+        self.all_projects = scan_roots
+            .iter()
+            .flat_map(|root| index.cached_projects(root))
+            .map(|mut project| {
+                project.rule = self.rules.evaluate(&project);
+                project
+            })
+            .collect();
+        self.all_projects.sort_by_key(|p| Reverse(p.disk_bytes));
+        self.apply_filter();
+        self.status_message = if self.all_projects.is_empty() {
+            format!("Scanning {} ...", roots_label(&scan_roots))
+        } else {
+            format!(
+                "Showing {} cached artifact folder(s) - verifying {} ...",
+                self.all_projects.len(),
+                roots_label(&scan_roots)
+            )
+        };
+
+        // Scan ALL projects regardless of threshold on the background executor,
+        // streaming progress and results back over a channel
+        let settings = ScanSettings::new(&self.config, filter);
+        let (tx, rx) = mpsc::channel();
+        cx.background_spawn(async move {
+            let scanner = settings
+                .scanner()
+                .with_cancel_token(cancel_token)
+                .with_index(index);
+            // Each root gets an equal share of the progress bar
+            let root_count = scan_roots.len() as f32;
+            let mut projects = Vec::new();
+            for (i, root) in scan_roots.iter().enumerate() {
+                projects.extend(scanner.scan_with_progress(
+                    root,
+                    |folder, progress| {
+                        tx.send(ScanMessage::Progress {
+                            folder: folder.to_string(),
+                            progress: (i as f32 + progress) / root_count,
+                        })
+                        .ok();
+                    },
+                    |project| {
+                        tx.send(ScanMessage::Found(Box::new(project.clone()))).ok();
+                    },
+                ));
+            }
+            projects.sort_by_key(|p| Reverse(p.disk_bytes));
+            tx.send(ScanMessage::Skipped(scanner.skipped_mounts())).ok();
+            tx.send(ScanMessage::Errors(scanner.errors())).ok();
+            if let Some(Err(e)) = scanner.into_index().map(|index| index.save()) {
+                eprintln!("Failed to save scan index: {}", e);
+            }
+            tx.send(ScanMessage::Done(projects)).ok();
+        })
+        .detach();
+
+        // Drain the channel on the UI thread a few times per second
+        cx.spawn(async move |this, cx| {
+            loop {
+                cx.background_executor().timer(SCAN_POLL_INTERVAL).await;
 
-        // Scan ALL projects regardless of threshold
-        // Note: Synchronous for now - async implementation causes type inference issues with GPUI
-        let scanner = Scanner::new(0);
-        self.all_projects = scanner.scan(&self.config.scan_path);
+                let mut messages = Vec::new();
+                let mut finished = false;
+                loop {
+                    match rx.try_recv() {
+                        Ok(message) => {
+                            finished |= matches!(message, ScanMessage::Done(_));
+                            messages.push(message);
+                        }
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => {
+                            finished = true;
+                            break;
+                        }
+                    }
+                }
+
+                let updated = this.update(cx, |view, cx| {
+                    let found = messages
+                        .iter()
+                        .any(|message| matches!(message, ScanMessage::Found(_)));
+                    for message in messages {
+                        view.handle_scan_message(message, cx);
+                    }
+                    // Re-filtering once per tick keeps a burst of finds cheap
+                    if found && view.is_scanning {
+                        view.apply_filter();
+                    }
+                    // The scanner went away without reporting, keep what was streamed
+                    if finished && view.is_scanning {
+                        let projects = view.all_projects.clone();
+                        view.finish_scan(projects, cx);
+                    }
+                    cx.notify();
+                });
+
+                if finished || updated.is_err() {
+                    break;
+                }
+            }
+        })
+        .detach();
+    }
+
+    fn handle_scan_message(&mut self, message: ScanMessage, cx: &mut Context<Self>) {
+        match message {
+            ScanMessage::Progress { folder, progress } => {
+                self.current_scan_folder = folder;
+                self.scan_progress = progress;
+            }
+            ScanMessage::Found(project) => {
+                self.upsert_project(*project);
+                self.status_message = format!(
+                    "Scanning {} ... found {} artifact folder(s) so far",
+                    roots_label(&self.config.scan_roots()),
+                    self.all_projects.len()
+                );
+            }
+            ScanMessage::Skipped(mounts) => self.skipped_mounts = mounts,
+            ScanMessage::Errors(errors) => self.scan_errors = errors,
+            ScanMessage::Done(projects) => self.finish_scan(projects, cx),
+        }
+    }
+
+    /// Add `project`, or replace the copy already listed while keeping its selection
+    fn upsert_project(&mut self, mut project: ProjectInfo) {
+        project.rule = self.rules.evaluate(&project);
+        match self
+            .all_projects
+            .iter_mut()
+            .find(|p| p.artifact_path == project.artifact_path)
+        {
+            Some(existing) => {
+                project.selected = existing.selected;
+                *existing = project;
+            }
+            None => self.all_projects.push(project),
+        }
+    }
 
+    fn finish_scan(&mut self, mut projects: Vec<ProjectInfo>, cx: &mut Context<Self>) {
+        let cancelled = self
+            .cancel_token
+            .take()
+            .is_some_and(|token| token.is_cancelled());
+        if cancelled {
+            // A cancelled scan did not get to verify every cached artifact, so
+            // keep the cached ones alongside what it found
+            projects = std::mem::take(&mut self.all_projects);
+            projects.sort_by_key(|p| Reverse(p.disk_bytes));
+        } else {
+            // Keep anything the user selected while results were streaming in
+            let selected: HashSet<PathBuf> = self
+                .all_projects
+                .iter()
+                .filter(|p| p.selected)
+                .map(|p| p.artifact_path.clone())
+                .collect();
+            for project in projects.iter_mut() {
+                project.selected = selected.contains(&project.artifact_path);
+            }
+        }
+
+        for project in projects.iter_mut() {
+            project.rule = self.rules.evaluate(project);
+        }
+        self.all_projects = projects;
         self.is_scanning = false;
         self.scan_progress = 1.0;
         self.current_scan_folder.clear();
 
         // Apply filter based on current threshold setting
         self.apply_filter();
+        if !cancelled && !self.rules.is_empty() {
+            self.select_by_rules();
+        }
+        if !self.skipped_mounts.is_empty() {
+            let skipped: Vec<String> = self.skipped_mounts.iter().map(|m| m.to_string()).collect();
+            self.status_message = format!(
+                "{} - skipped {} mount(s): {}",
+                self.status_message,
+                skipped.len(),
+                skipped.join(", ")
+            );
+        }
+        if !self.scan_errors.is_empty() {
+            self.status_message = format!(
+                "{} - ⚠ {} path(s) could not be read or parsed, see [ERRORS]",
+                self.status_message,
+                self.scan_errors.len()
+            );
+        }
+        if cancelled {
+            self.status_message = format!("⏹ Scan cancelled - {}", self.status_message);
+        } else if watcher::is_supported() {
+            self.start_watching(cx);
+        }
     }
 
-    pub fn apply_filter(&mut self) {
-        if self.threshold_enabled {
-            self.projects = self
-                .all_projects
-                .iter()
-                .filter(|p| p.days_old() >= self.config.threshold_days as u64)
-                .cloned()
-                .collect();
-        } else {
-            self.projects = self.all_projects.clone();
+    /// Keep the results current from filesystem events until the next scan
+    fn start_watching(&mut self, cx: &mut Context<Self>) {
+        let token = CancelToken::new();
+        self.watch_token = Some(token.clone());
+        let scan_roots = self.config.scan_roots();
+        let watched_roots = scan_roots.clone();
+        let settings =
+            ScanSettings::new(&self.config, self.config.path_filter().unwrap_or_default());
+        let setup_settings = settings.clone();
+
+        cx.spawn(async move |this, cx| {
+            // Adding the watches walks the whole tree again, so do it off the UI thread
+            let setup = cx.background_executor().spawn(async move {
+                let watcher = Watcher::new()?;
+                let scanner = setup_settings.scanner();
+                for root in &scan_roots {
+                    watcher.watch_tree(root, scanner.root_position(root), &scanner)?;
+                }
+                Ok::<_, io::Error>(watcher)
+            });
+            let watcher = match setup.await {
+                Ok(watcher) => watcher,
+                Err(e) => {
+                    this.update(cx, |view, cx| {
+                        view.status_message =
+                            format!("{} - live updates off: {}", view.status_message, e);
+                        cx.notify();
+                    })
+                    .ok();
+                    return;
+                }
+            };
+
+            loop {
+                cx.background_executor().timer(WATCH_POLL_INTERVAL).await;
+                if token.is_cancelled() {
+                    break;
+                }
+
+                let events = watcher.poll();
+                if events.is_empty() {
+                    continue;
+                }
+                if events.contains(&WatchEvent::Overflowed) {
+                    this.update(cx, |view, cx| view.scan_for_projects(cx)).ok();
+                    break;
+                }
+
+                let Ok(known) = this.update(cx, |view, _| {
+                    view.all_projects
+                        .iter()
+                        .map(|p| p.artifact_path.clone())
+                        .collect::<HashSet<PathBuf>>()
+                }) else {
+                    break;
+                };
+                let resolving = watcher.clone();
+                let settings = settings.clone();
+                let roots = watched_roots.clone();
+                let updates = cx
+                    .background_executor()
+                    .spawn(async move {
+                        watcher::resolve(events, &settings.scanner(), &resolving, &known, &roots)
+                    })
+                    .await;
+
+                let updated = this.update(cx, |view, cx| {
+                    if !token.is_cancelled() {
+                        view.apply_watch_updates(updates);
+                        cx.notify();
+                    }
+                });
+                if updated.is_err() {
+                    break;
+                }
+            }
+        })
+        .detach();
+    }
+
+    fn stop_watching(&mut self) {
+        if let Some(token) = self.watch_token.take() {
+            token.cancel();
         }
+    }
+
+    fn apply_watch_updates(&mut self, updates: Vec<Update>) {
+        if updates.is_empty() {
+            return;
+        }
+        for update in updates {
+            match update {
+                Update::Found(project) => self.upsert_project(*project),
+                Update::Removed(path) => self
+                    .all_projects
+                    .retain(|p| !p.artifact_path.starts_with(&path)),
+            }
+        }
+        self.all_projects.sort_by_key(|p| Reverse(p.disk_bytes));
+        self.apply_filter();
+    }
+
+    pub fn apply_filter(&mut self) {
+        self.projects = self
+            .all_projects
+            .iter()
+            .filter(|p| p.rule_action() != Some(RuleAction::Ignore))
+            .filter(|p| !self.threshold_enabled || self.config.result_filter().matches(p))
+            .cloned()
+            .collect();
+        listing::arrange(
+            &mut self.projects,
+            self.config.list_order(),
+            &self.config.scan_roots(),
+        );
 
         let total_size_gb: f64 = self.projects.iter().map(|p| p.size_gb()).sum();
         self.status_message = format!(
-            "Found {} node_modules folder(s) - Total: {:.2} GB",
+            "Found {} artifact folder(s) - Total: {:.2} GB",
             self.projects.len(),
             total_size_gb
         );
     }
 
-    pub fn delete_selected(&mut self) {
-        let mut deleted_count = 0;
-        let mut failed_count = 0;
-        let mut freed_gb = 0.0;
+    pub fn delete_selected(&mut self, cx: &mut Context<Self>) {
+        if self.is_scanning || self.is_deleting || self.is_previewing {
+            return;
+        }
 
-        self.projects.retain(|project| {
-            if project.selected {
-                match fs::remove_dir_all(&project.node_modules_path) {
-                    Ok(_) => {
-                        deleted_count += 1;
-                        freed_gb += project.size_gb();
-                        false // Remove from list if deleted
-                    }
-                    Err(e) => {
-                        eprintln!(
-                            "Failed to delete {}: {}",
-                            project.node_modules_path.display(),
-                            e
-                        );
-                        failed_count += 1;
-                        true // Keep in list if deletion failed
-                    }
+        let with_local_work = self
+            .projects
+            .iter()
+            .filter(|p| p.selected && p.git.as_ref().is_some_and(|git| git.has_local_work()))
+            .count();
+        if with_local_work > 0 && !self.confirm_local_work {
+            self.confirm_local_work = true;
+            self.status_message = format!(
+                "⚠ {} selected project(s) have uncommitted, stashed or unpushed work - click again to remove anyway",
+                with_local_work
+            );
+            return;
+        }
+        self.confirm_local_work = false;
+
+        self.is_deleting = true;
+        self.preview = None;
+        self.status_message = format!("Removing {} artifact(s) ...", self.selected_count());
+        let cancel_token = CancelToken::new();
+        self.cancel_token = Some(cancel_token.clone());
+
+        let mut selected: Vec<ProjectInfo> = self
+            .projects
+            .iter()
+            .filter(|p| p.selected)
+            .cloned()
+            .collect();
+        // Selected projects hidden by the filter are not sent, and must stay
+        let sent: HashSet<PathBuf> = selected.iter().map(|p| p.artifact_path.clone()).collect();
+        let mode = self.config.delete_mode;
+        let task = cx.background_spawn(async move {
+            let summary = cleaner::delete_selected(&mut selected, mode, &cancel_token);
+            (selected, summary)
+        });
+
+        cx.spawn(async move |this, cx| {
+            let (remaining, summary) = task.await;
+            this.update(cx, |view, cx| {
+                view.finish_delete(sent, remaining, summary);
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// `sent` holds the artifacts handed to the cleaner and `remaining` the
+    /// ones among them that failed or were skipped
+    fn finish_delete(
+        &mut self,
+        sent: HashSet<PathBuf>,
+        remaining: Vec<ProjectInfo>,
+        summary: CleanSummary,
+    ) {
+        let remaining: HashSet<PathBuf> = remaining.into_iter().map(|p| p.artifact_path).collect();
+        let removed = |p: &ProjectInfo| {
+            sent.contains(&p.artifact_path) && !remaining.contains(&p.artifact_path)
+        };
+        self.projects.retain(|p| !removed(p));
+        self.all_projects.retain(|p| !removed(p));
+
+        self.is_deleting = false;
+        self.cancel_token = None;
+        self.status_message = summary.status_message();
+        if summary.mode == DeleteMode::Quarantine {
+            self.quarantine = Quarantine::open().entries().to_vec();
+        }
+    }
+
+    /// Ask the running scan or deletion to stop; finished work is kept
+    pub fn cancel(&mut self) {
+        if let Some(token) = &self.cancel_token {
+            token.cancel();
+            self.status_message = "Cancelling ...".to_string();
+        }
+    }
+
+    /// Report what deleting the selection would remove, without touching disk
+    pub fn preview_selected(&mut self, cx: &mut Context<Self>) {
+        if self.is_deleting || self.is_previewing {
+            return;
+        }
+        self.is_previewing = true;
+        self.status_message = format!("Previewing {} artifact(s) ...", self.selected_count());
+
+        let selected: Vec<ProjectInfo> = self
+            .projects
+            .iter()
+            .filter(|p| p.selected)
+            .cloned()
+            .collect();
+        let mode = self.config.delete_mode;
+        let task = cx.background_spawn(async move { preview::preview_selected(&selected, mode) });
+
+        cx.spawn(async move |this, cx| {
+            let report = task.await;
+            this.update(cx, |view, cx| {
+                view.is_previewing = false;
+                view.status_message = report.status_message();
+                view.preview = Some(report);
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    pub fn close_preview(&mut self) {
+        self.preview = None;
+    }
+
+    /// Cycle Trash -> Quarantine -> Permanent, skipping Trash where unsupported
+    pub fn toggle_delete_mode(&mut self) {
+        self.config.delete_mode = match self.config.delete_mode {
+            DeleteMode::Trash => DeleteMode::Quarantine,
+            DeleteMode::Quarantine => DeleteMode::Permanent,
+            DeleteMode::Permanent if trash::is_supported() => DeleteMode::Trash,
+            DeleteMode::Permanent => DeleteMode::Quarantine,
+        };
+        let _ = self.config.save();
+    }
+
+    pub fn toggle_quarantine_view(&mut self) {
+        self.show_quarantine = !self.show_quarantine;
+        self.show_patterns = false;
+        self.show_errors = false;
+        if self.show_quarantine {
+            self.quarantine = Quarantine::open().entries().to_vec();
+        }
+    }
+
+    pub fn toggle_patterns_view(&mut self) {
+        self.show_patterns = !self.show_patterns;
+        self.show_quarantine = false;
+        self.show_errors = false;
+    }
+
+    pub fn toggle_errors_view(&mut self) {
+        self.show_errors = !self.show_errors;
+        self.show_quarantine = false;
+        self.show_patterns = false;
+    }
+
+    /// Edit the pattern being typed; Enter adds it, Escape clears it
+    pub fn handle_pattern_key(&mut self, event: &KeyDownEvent) {
+        let keystroke = &event.keystroke;
+        match keystroke.key.as_str() {
+            "enter" => self.add_pattern(),
+            "escape" => self.pattern_input.clear(),
+            "backspace" => {
+                self.pattern_input.pop();
+            }
+            _ if keystroke.modifiers.control || keystroke.modifiers.platform => {}
+            _ => {
+                if let Some(text) = &keystroke.key_char {
+                    self.pattern_input.push_str(text);
                 }
-            } else {
-                true // Keep unselected projects
             }
-        });
+        }
+    }
 
-        self.status_message = if failed_count > 0 {
-            format!(
-                "✅ Deleted {} node_modules ({:.2} GB freed), ❌ {} failed",
-                deleted_count, freed_gb, failed_count
-            )
-        } else {
-            format!(
-                "✅ Successfully deleted {} node_modules - Freed {:.2} GB",
-                deleted_count, freed_gb
-            )
+    pub fn add_pattern(&mut self) {
+        let pattern = self.pattern_input.trim().to_string();
+        if pattern.is_empty() {
+            return;
+        }
+        if let Err(e) = patterns::validate(&pattern) {
+            self.status_message = format!("❌ {}", e);
+            return;
+        }
+        self.config.scan_patterns.push(pattern.clone());
+        self.pattern_input.clear();
+        self.patterns_changed(format!("Added pattern '{}'", pattern));
+    }
+
+    pub fn remove_pattern(&mut self, index: usize) {
+        if index < self.config.scan_patterns.len() {
+            let pattern = self.config.scan_patterns.remove(index);
+            self.patterns_changed(format!("Removed pattern '{}'", pattern));
+        }
+    }
+
+    pub fn reset_patterns(&mut self) {
+        self.config.scan_patterns = patterns::default_patterns();
+        self.patterns_changed("Restored the default patterns".to_string());
+    }
+
+    fn patterns_changed(&mut self, message: String) {
+        self.status_message = format!("{} - scan again to apply", message);
+        if let Err(e) = self.config.save() {
+            eprintln!("Failed to save config: {}", e);
+        }
+    }
+
+    pub fn restore_quarantined(&mut self, id: &str) {
+        let mut quarantine = Quarantine::open();
+        self.status_message = match quarantine.restore(id) {
+            Ok(path) => format!("✅ Restored {}", path.display()),
+            Err(e) => format!("❌ Failed to restore: {}", e),
+        };
+        self.quarantine = quarantine.entries().to_vec();
+    }
+
+    pub fn increase_quarantine_days(&mut self) {
+        self.config.quarantine_days += 1;
+        let _ = self.config.save();
+    }
+
+    pub fn decrease_quarantine_days(&mut self) {
+        if self.config.quarantine_days > 1 {
+            self.config.quarantine_days -= 1;
+            let _ = self.config.save();
+        }
+    }
+
+    /// Export the listed projects, picking the format from the file extension
+    pub fn export_report(&mut self, path: PathBuf) {
+        let format = ReportFormat::from_path(&path).unwrap_or(ReportFormat::Json);
+        let result = File::create(&path)
+            .and_then(|file| report::write_report(&self.projects, format, BufWriter::new(file)));
+
+        self.status_message = match result {
+            Ok(()) => format!(
+                "Exported {} artifact(s) to {}",
+                self.projects.len(),
+                path.display()
+            ),
+            Err(e) => format!("❌ Failed to export report: {}", e),
         };
     }
 
     pub fn toggle_project(&mut self, index: usize) {
+        if self.is_deleting {
+            return;
+        }
+        if let Some(project) = self.projects.get(index) {
+            if self.protect.protects(project) {
+                self.status_message = format!(
+                    "📌 {} is protected - unpin it to select it",
+                    display_root(&project.project_path)
+                );
+                return;
+            }
+            if let Some(rule) = project
+                .rule
+                .as_ref()
+                .filter(|rule| rule.action == RuleAction::Protect)
+            {
+                self.status_message = format!(
+                    "📌 {} is protected by the rule '{}'",
+                    display_root(&project.project_path),
+                    rule.name
+                );
+                return;
+            }
+        }
+        self.confirm_local_work = false;
         if let Some(project) = self.projects.get_mut(index) {
             project.selected = !project.selected;
+
+            // Mirror into the cache so re-filtering keeps the selection
+            if let Some(cached) = self
+                .all_projects
+                .iter_mut()
+                .find(|p| p.artifact_path == project.artifact_path)
+            {
+                cached.selected = project.selected;
+            }
         }
     }
 
+    /// Pin or unpin the project at `index`. Pinned projects are deselected
+    /// and cannot be selected again until they are unpinned.
+    pub fn toggle_protected(&mut self, index: usize) {
+        if self.is_deleting {
+            return;
+        }
+        let Some(project) = self.projects.get(index) else {
+            return;
+        };
+        let path = project.project_path.clone();
+        let label = display_root(&path);
+
+        if let Some(position) = self
+            .config
+            .protected
+            .iter()
+            .position(|entry| protect::expand_home(entry.trim()) == path)
+        {
+            self.config.protected.remove(position);
+            self.status_message = format!("Unpinned {}", label);
+        } else if self.protect.protects(project) {
+            self.status_message = format!(
+                "📌 {} is protected by a folder or glob in the config's protected list",
+                label
+            );
+            return;
+        } else if let Some(rule) = project
+            .rule
+            .as_ref()
+            .filter(|rule| rule.action == RuleAction::Protect)
+        {
+            self.status_message = format!("📌 {} is protected by the rule '{}'", label, rule.name);
+            return;
+        } else {
+            self.config.protected.push(label.clone());
+            self.status_message = format!("📌 Pinned {} - it will never be cleaned", label);
+        }
+
+        let protect = ProtectList::new(&self.config.protected);
+        if protect.invalid().len() > self.protect.invalid().len() {
+            // A path with glob characters in its name
+            self.config.protected.pop();
+            self.status_message = format!(
+                "❌ Cannot pin {}: {}",
+                label,
+                protect.invalid().last().cloned().unwrap_or_default()
+            );
+            return;
+        }
+        self.protect = protect;
+        for project in self.projects.iter_mut().chain(self.all_projects.iter_mut()) {
+            if self.protect.protects(project) {
+                project.selected = false;
+            }
+        }
+        self.confirm_local_work = false;
+        let _ = self.config.save();
+    }
+
+    /// Whether `project` can never be selected, because it is pinned, listed
+    /// as protected or matched by a protect rule
+    pub fn is_protected(&self, project: &ProjectInfo) -> bool {
+        self.protect.protects(project) || project.rule_action() == Some(RuleAction::Protect)
+    }
+
+    /// Reload the rules from the config file, match them against every
+    /// project and select what select rules match. Selections made by hand
+    /// are kept, except on projects a protect rule now matches.
+    pub fn apply_rules(&mut self) {
+        if self.is_scanning || self.is_deleting {
+            return;
+        }
+        let rules = Config::load().rules;
+        match RuleSet::new(&rules) {
+            Ok(compiled) => {
+                self.config.rules = rules;
+                self.rules = compiled;
+            }
+            Err(e) => {
+                self.status_message = format!("❌ {} - rules left as they were", e);
+                return;
+            }
+        }
+        if self.rules.is_empty() {
+            self.status_message = "No rules in the config - add some under \"rules\"".to_string();
+            return;
+        }
+        for project in self.all_projects.iter_mut() {
+            project.rule = self.rules.evaluate(project);
+        }
+        self.apply_filter();
+        self.select_by_rules();
+    }
+
+    /// Select the listed projects select rules match and deselect the ones
+    /// protect rules match
+    fn select_by_rules(&mut self) {
+        let mut selected = 0;
+        for project in self.projects.iter_mut() {
+            match project.rule_action() {
+                Some(RuleAction::Select) if !self.protect.protects(project) => {
+                    project.selected = true;
+                    selected += 1;
+                }
+                Some(RuleAction::Protect) => project.selected = false,
+                _ => {}
+            }
+        }
+        // Mirror into the cache so re-filtering keeps the selection
+        let selection: HashMap<&Path, bool> = self
+            .projects
+            .iter()
+            .map(|p| (p.artifact_path.as_path(), p.selected))
+            .collect();
+        for project in self.all_projects.iter_mut() {
+            if let Some(&selected) = selection.get(project.artifact_path.as_path()) {
+                project.selected = selected;
+            }
+        }
+        self.confirm_local_work = false;
+        self.status_message = format!(
+            "{} - rules selected {} artifact(s)",
+            self.status_message, selected
+        );
+    }
+
     pub fn total_selected_size_gb(&self) -> f64 {
         self.projects
             .iter()
@@ -147,10 +1022,8 @@ impl StorageCleaner {
     pub fn increase_threshold(&mut self) {
         if self.all_projects.is_empty() {
             // No data to snap to, just increment by 1
-            if self.config.threshold_days < 365 {
-                self.config.threshold_days += 1;
-                let _ = self.config.save();
-            }
+            self.config.threshold_days = self.config.threshold_days.saturating_add(1);
+            let _ = self.config.save();
             return;
         }
 
@@ -169,9 +1042,9 @@ impl StorageCleaner {
             .find(|&&days| days > self.config.threshold_days)
         {
             self.config.threshold_days = next_value;
-        } else if self.config.threshold_days < 365 {
-            // If no higher value exists, go to 365 (max)
-            self.config.threshold_days = 365;
+        } else {
+            // Past the oldest project, step by a day
+            self.config.threshold_days = self.config.threshold_days.saturating_add(1);
         }
 
         let _ = self.config.save();
@@ -213,17 +1086,121 @@ impl StorageCleaner {
         self.apply_filter();
     }
 
-    #[allow(dead_code)]
-    pub fn set_threshold(&mut self, days: u32) {
-        if days <= 365 {
-            self.config.threshold_days = days;
-            let _ = self.config.save();
-            if !self.all_projects.is_empty() {
-                self.apply_filter();
+    /// Start typing into `field`, beginning with its current value
+    pub fn edit_filter(&mut self, field: FilterField) {
+        self.filter_edit = Some(field);
+        self.filter_input = self.filter_label(field);
+    }
+
+    /// The value `field` shows, `ANY` when it is not set
+    pub fn filter_label(&self, field: FilterField) -> String {
+        match field {
+            FilterField::MinAge => filters::format_days(self.config.threshold_days),
+            FilterField::MaxAge => self
+                .config
+                .max_age_days
+                .map(filters::format_days)
+                .unwrap_or_else(|| "ANY".to_string()),
+            FilterField::MinSize => self
+                .config
+                .min_size_bytes
+                .map(filters::format_size)
+                .unwrap_or_else(|| "ANY".to_string()),
+        }
+    }
+
+    /// Edit the limit being typed; Enter applies it, Escape drops it
+    pub fn handle_filter_key(&mut self, event: &KeyDownEvent) {
+        let keystroke = &event.keystroke;
+        match keystroke.key.as_str() {
+            "enter" => self.commit_filter(),
+            "escape" => self.filter_edit = None,
+            "backspace" => {
+                self.filter_input.pop();
+            }
+            _ if keystroke.modifiers.control || keystroke.modifiers.platform => {}
+            _ => {
+                if let Some(text) = &keystroke.key_char {
+                    self.filter_input.push_str(text);
+                }
             }
         }
     }
 
+    /// Apply the typed limit. An empty value, or `any`, clears the age and
+    /// size limits and sets the threshold to 0.
+    pub fn commit_filter(&mut self) {
+        let Some(field) = self.filter_edit else {
+            return;
+        };
+        let input = self.filter_input.trim();
+        let result = match field {
+            FilterField::MinAge => parse_limit(input, filters::parse_days)
+                .map(|days| self.config.threshold_days = days.unwrap_or(0)),
+            FilterField::MaxAge => {
+                parse_limit(input, filters::parse_days).map(|days| self.config.max_age_days = days)
+            }
+            FilterField::MinSize => parse_limit(input, filters::parse_size)
+                .map(|bytes| self.config.min_size_bytes = bytes),
+        };
+        if let Err(e) = result {
+            // Keep the input so the mistake can be fixed
+            self.status_message = format!("❌ {}", e);
+            return;
+        }
+
+        self.filter_edit = None;
+        let _ = self.config.save();
+        self.apply_filter();
+    }
+
+    /// Sort by the next key, in that key's usual direction
+    pub fn cycle_sort_key(&mut self) {
+        self.config.sort_key = self.config.sort_key.next();
+        self.config.sort_ascending = self.config.sort_key.ascending_by_default();
+        self.list_order_changed();
+    }
+
+    pub fn toggle_sort_order(&mut self) {
+        self.config.sort_ascending = !self.config.sort_ascending;
+        self.list_order_changed();
+    }
+
+    pub fn cycle_group_by(&mut self) {
+        self.config.group_by = self.config.group_by.next();
+        self.collapsed_groups.clear();
+        self.list_order_changed();
+    }
+
+    /// Fold or unfold the group at `path`
+    pub fn toggle_group(&mut self, path: &Path) {
+        if !self.collapsed_groups.remove(path) {
+            self.collapsed_groups.insert(path.to_path_buf());
+        }
+    }
+
+    fn list_order_changed(&mut self) {
+        let _ = self.config.save();
+        listing::arrange(
+            &mut self.projects,
+            self.config.list_order(),
+            &self.config.scan_roots(),
+        );
+    }
+
+    /// Measure ages by the next activity signal and rescan, since ages are
+    /// worked out while scanning
+    pub fn cycle_activity_signal(&mut self, cx: &mut Context<Self>) {
+        if self.is_scanning || self.is_deleting {
+            return;
+        }
+        self.config.activity_signal = self.config.activity_signal.next();
+        let _ = self.config.save();
+        if !self.all_projects.is_empty() {
+            self.scan_for_projects(cx);
+        }
+    }
+
     pub fn toggle_threshold(&mut self) {
         self.threshold_enabled = !self.threshold_enabled;
         if !self.all_projects.is_empty() {
@@ -231,3 +1208,33 @@ impl StorageCleaner {
         }
     }
 }
+
+/// A typed limit, `None` when it is empty or `any`
+fn parse_limit<T>(input: &str, parse: fn(&str) -> Result<T, String>) -> Result<Option<T>, String> {
+    if input.is_empty() || input.eq_ignore_ascii_case("any") {
+        Ok(None)
+    } else {
+        parse(input).map(Some)
+    }
+}
+
+/// Roots for status messages, e.g. `~/work, ~/oss`
+pub fn roots_label(roots: &[PathBuf]) -> String {
+    if roots.is_empty() {
+        return "no roots".to_string();
+    }
+    roots
+        .iter()
+        .map(|root| display_root(root))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A root path with the home directory shortened to `~`
+pub fn display_root(root: &Path) -> String {
+    match dirs::home_dir().and_then(|home| root.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(relative) if relative.as_os_str().is_empty() => "~".to_string(),
+        Some(relative) => format!("~/{}", relative.display()),
+        None => root.display().to_string(),
+    }
+}
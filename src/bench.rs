@@ -0,0 +1,137 @@
+//! Benchmark harness for the scanner.
+//!
+//! Generates a synthetic tree of projects with `node_modules` under the temp
+//! directory, then times a single-threaded scan against a parallel one.
+
+use crate::scanner::Scanner;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub struct BenchOptions {
+    pub projects: usize,
+    pub packages: usize,
+    pub files: usize,
+    pub rounds: usize,
+    /// Threads for the parallel run, 0 for one per CPU
+    pub threads: usize,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            projects: 200,
+            packages: 20,
+            files: 10,
+            rounds: 3,
+            threads: 0,
+        }
+    }
+}
+
+struct Timing {
+    threads: usize,
+    best: Duration,
+    mean: Duration,
+    artifacts: usize,
+}
+
+pub fn run(options: &BenchOptions) -> io::Result<()> {
+    let root =
+        std::env::temp_dir().join(format!("dev-storage-cleaner-bench-{}", std::process::id()));
+
+    let result = generate(&root, options).map(|file_count| {
+        println!(
+            "Generated {} projects, {} files in {}",
+            options.projects,
+            file_count,
+            root.display()
+        );
+        let parallel_threads = if options.threads == 0 {
+            rayon::current_num_threads()
+        } else {
+            options.threads
+        };
+        [1, parallel_threads]
+            .iter()
+            .map(|&threads| time_scans(&root, threads, options.rounds))
+            .collect::<Vec<_>>()
+    });
+    fs::remove_dir_all(&root).ok();
+    let timings = result?;
+
+    println!(
+        "{:>8}  {:>10}  {:>10}  {:>9}",
+        "THREADS", "BEST", "MEAN", "ARTIFACTS"
+    );
+    for timing in &timings {
+        println!(
+            "{:>8}  {:>7} ms  {:>7} ms  {:>9}",
+            timing.threads,
+            timing.best.as_millis(),
+            timing.mean.as_millis(),
+            timing.artifacts
+        );
+    }
+    if let [sequential, parallel] = timings.as_slice() {
+        println!(
+            "Speedup: {:.2}x",
+            sequential.best.as_secs_f64() / parallel.best.as_secs_f64().max(f64::EPSILON)
+        );
+    }
+    Ok(())
+}
+
+/// Lay out `projects` projects across a few group folders, each with a
+/// `package.json`, some sources and a `node_modules` of `packages` packages.
+fn generate(root: &Path, options: &BenchOptions) -> io::Result<usize> {
+    let contents = "x".repeat(1024);
+    let mut file_count = 0;
+
+    for p in 0..options.projects {
+        let project = root
+            .join(format!("group-{}", p % 10))
+            .join(format!("project-{}", p));
+        fs::create_dir_all(project.join("src"))?;
+        fs::write(project.join("package.json"), "{}")?;
+        fs::write(project.join("src").join("index.js"), &contents)?;
+        file_count += 2;
+
+        for k in 0..options.packages {
+            let package = project
+                .join("node_modules")
+                .join(format!("package-{}", k))
+                .join("lib");
+            fs::create_dir_all(&package)?;
+            for f in 0..options.files {
+                fs::write(package.join(format!("file-{}.js", f)), &contents)?;
+                file_count += 1;
+            }
+        }
+    }
+
+    Ok(file_count)
+}
+
+/// One warm-up scan so every run sees a hot page cache, then `rounds` timed scans
+fn time_scans(root: &Path, threads: usize, rounds: usize) -> Timing {
+    let scanner = Scanner::new(0).with_threads(threads);
+    let artifacts = scanner.scan(root).len();
+
+    let durations: Vec<Duration> = (0..rounds.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            scanner.scan(root);
+            start.elapsed()
+        })
+        .collect();
+
+    Timing {
+        threads,
+        best: durations.iter().min().copied().unwrap_or_default(),
+        mean: durations.iter().sum::<Duration>() / durations.len() as u32,
+        artifacts,
+    }
+}
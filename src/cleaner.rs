@@ -0,0 +1,264 @@
+use crate::cancel::CancelToken;
+use crate::ignore;
+use crate::protect::ProtectList;
+use crate::quarantine::Quarantine;
+use crate::rules::{RuleAction, RuleSet};
+use crate::scanner::ProjectInfo;
+use crate::trash;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// How selected artifacts are removed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteMode {
+    /// Move to the desktop trash so a misclick can be undone
+    Trash,
+    /// Move to the app's own quarantine, restorable until it is purged
+    Quarantine,
+    /// Remove from disk immediately
+    Permanent,
+}
+
+impl Default for DeleteMode {
+    fn default() -> Self {
+        if trash::is_supported() {
+            DeleteMode::Trash
+        } else {
+            DeleteMode::Permanent
+        }
+    }
+}
+
+/// Outcome of a bulk deletion, shared by the UI and the headless CLI
+#[derive(Debug)]
+pub struct CleanSummary {
+    pub mode: DeleteMode,
+    pub deleted_count: usize,
+    pub failed_count: usize,
+    /// Selected artifacts left untouched because the run was cancelled
+    pub skipped_count: usize,
+    /// Selected artifacts left untouched because a `.devstorageignore` or an
+    /// ignore rule now excludes them
+    pub ignored_count: usize,
+    /// Selected artifacts left untouched because their project is protected
+    /// by the config's list or a rule
+    pub protected_count: usize,
+    pub freed_gb: f64,
+    /// Why nothing was removed, when what protects projects could not be read
+    pub refused: Option<String>,
+}
+
+impl CleanSummary {
+    pub fn status_message(&self) -> String {
+        if let Some(reason) = &self.refused {
+            return format!("❌ Nothing removed: {}", reason);
+        }
+        let done = match self.mode {
+            DeleteMode::Trash => format!(
+                "Trashed {} artifact(s) - {:.2} GB moved to trash",
+                self.deleted_count, self.freed_gb
+            ),
+            DeleteMode::Quarantine => format!(
+                "Quarantined {} artifact(s) - {:.2} GB held until purged",
+                self.deleted_count, self.freed_gb
+            ),
+            DeleteMode::Permanent => format!(
+                "Deleted {} artifact(s) - Freed {:.2} GB",
+                self.deleted_count, self.freed_gb
+            ),
+        };
+
+        let mut message = format!("✅ {}", done);
+        if self.failed_count > 0 {
+            message.push_str(&format!(", ❌ {} failed", self.failed_count));
+        }
+        if self.skipped_count > 0 {
+            message.push_str(&format!(", ⏹ cancelled with {} left", self.skipped_count));
+        }
+        if self.protected_count > 0 {
+            message.push_str(&format!(", 📌 {} protected", self.protected_count));
+        }
+        if self.ignored_count > 0 {
+            message.push_str(&format!(
+                ", 🚫 {} ignored by .devstorageignore or a rule",
+                self.ignored_count
+            ));
+        }
+        message
+    }
+}
+
+/// Why a selected artifact is left alone
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hold {
+    /// Its project is protected by the config's list or a rule
+    Protected,
+    /// A `.devstorageignore` or an ignore rule excludes it
+    Ignored,
+}
+
+/// The protect list and rules as saved on disk, which decide what a delete
+/// and its dry run leave alone
+pub struct Holds {
+    protect: ProtectList,
+    rules: RuleSet,
+}
+
+impl Holds {
+    /// Read from disk rather than trusting the caller, so nothing that sets
+    /// `selected` can get around it. Fails when the config cannot be read or
+    /// the protect list or rules do not compile, since going ahead would drop
+    /// whatever they protect.
+    pub fn load() -> Result<Self, String> {
+        Ok(Self {
+            protect: ProtectList::load()?,
+            rules: RuleSet::load()?,
+        })
+    }
+
+    /// Whether `project` has to stay, and why
+    pub fn check(&self, project: &ProjectInfo) -> Option<Hold> {
+        let action = if self.rules.is_empty() {
+            None
+        } else {
+            self.rules.action_for(project)
+        };
+        if self.protect.protects(project) || action == Some(RuleAction::Protect) {
+            Some(Hold::Protected)
+        } else if action == Some(RuleAction::Ignore) || ignore::is_ignored(&project.artifact_path) {
+            // The file may have been added after the scan, so check again
+            Some(Hold::Ignored)
+        } else {
+            None
+        }
+    }
+}
+
+/// Remove the artifact of every selected project.
+///
+/// Removed projects are dropped from `projects`; projects whose removal
+/// failed, that are protected, that a `.devstorageignore` or rule excludes, or that
+/// were never reached because `cancel` was triggered, stay in the list. Nothing
+/// is removed when the saved protections cannot be read.
+pub fn delete_selected(
+    projects: &mut Vec<ProjectInfo>,
+    mode: DeleteMode,
+    cancel: &CancelToken,
+) -> CleanSummary {
+    let mut summary = CleanSummary {
+        mode,
+        deleted_count: 0,
+        failed_count: 0,
+        skipped_count: 0,
+        ignored_count: 0,
+        protected_count: 0,
+        freed_gb: 0.0,
+        refused: None,
+    };
+    let holds = match Holds::load() {
+        Ok(holds) => holds,
+        Err(e) => {
+            summary.refused = Some(e);
+            return summary;
+        }
+    };
+    let mut quarantine: Option<Quarantine> = None;
+
+    projects.retain(|project| {
+        let hold = if project.selected {
+            holds.check(project)
+        } else {
+            None
+        };
+        if project.selected && cancel.is_cancelled() {
+            summary.skipped_count += 1;
+            true
+        } else if hold == Some(Hold::Protected) {
+            summary.protected_count += 1;
+            true
+        } else if hold == Some(Hold::Ignored) {
+            summary.ignored_count += 1;
+            true
+        } else if project.selected {
+            let result = match mode {
+                DeleteMode::Trash => trash::move_to_trash(&project.artifact_path),
+                DeleteMode::Quarantine => quarantine
+                    .get_or_insert_with(Quarantine::open)
+                    .add(&project.artifact_path, project.disk_bytes),
+                DeleteMode::Permanent => fs::remove_dir_all(&project.artifact_path),
+            };
+            match result {
+                Ok(_) => {
+                    summary.deleted_count += 1;
+                    summary.freed_gb += project.size_gb();
+                    false // Remove from list if deleted
+                }
+                Err(e) => {
+                    eprintln!(
+                        "Failed to remove {}: {}",
+                        project.artifact_path.display(),
+                        e
+                    );
+                    summary.failed_count += 1;
+                    true // Keep in list if deletion failed
+                }
+            }
+        } else {
+            true // Keep unselected projects
+        }
+    });
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activity::ActivitySignal;
+    use crate::detectors::ArtifactKind;
+    use crate::rules::Rule;
+    use std::path::PathBuf;
+    use std::time::SystemTime;
+
+    fn project(artifact: &str) -> ProjectInfo {
+        let artifact_path = PathBuf::from(artifact);
+        ProjectInfo {
+            project_path: artifact_path.parent().unwrap().to_path_buf(),
+            artifact_path,
+            kind: ArtifactKind::NodeModules,
+            marker: String::new(),
+            last_modified: SystemTime::now(),
+            last_active: SystemTime::now(),
+            activity: ActivitySignal::Artifact,
+            git: None,
+            rule: None,
+            size_bytes: 0,
+            disk_bytes: 0,
+            selected: true,
+        }
+    }
+
+    #[test]
+    fn protect_list_overrides_a_select_rule() {
+        let select_all = Rule {
+            name: "everything".to_string(),
+            kinds: Vec::new(),
+            older_than_days: None,
+            min_size_mb: None,
+            path: None,
+            git_clean: None,
+            has_lockfile: None,
+            action: RuleAction::Select,
+        };
+        let holds = Holds {
+            protect: ProtectList::new(&["/dsc-holds/keep".to_string()]),
+            rules: RuleSet::new(&[select_all]).unwrap(),
+        };
+        assert_eq!(
+            holds.check(&project("/dsc-holds/keep/app/node_modules")),
+            Some(Hold::Protected)
+        );
+        assert_eq!(holds.check(&project("/dsc-holds/other/node_modules")), None);
+    }
+}
@@ -0,0 +1,669 @@
+use crate::activity::ActivitySignal;
+use crate::bench::{self, BenchOptions};
+use crate::cancel::CancelToken;
+use crate::cleaner::{self, DeleteMode};
+use crate::config::Config;
+use crate::errors::ScanErrorKind;
+use crate::filters::{self, ResultFilter};
+use crate::index::ScanIndex;
+use crate::patterns::PathFilter;
+use crate::preview::{self, DryRunReport};
+use crate::protect::{self, ProtectList};
+use crate::quarantine::Quarantine;
+use crate::report::{self, ReportFormat};
+use crate::rules::{RuleAction, RuleSet};
+use crate::scanner::{ProjectInfo, Scanner};
+use crate::trash;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+// Exit codes
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILED: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_ABORTED: i32 = 3;
+
+/// Scan errors printed before the rest are summarised as a count
+const MAX_ERRORS_SHOWN: usize = 20;
+
+const USAGE: &str = "\
+Usage: dev-storage-cleaner [COMMAND] [OPTIONS]
+
+Without a command the desktop app is opened.
+
+Commands:
+  scan     List build artifacts older than the threshold
+  clean    Delete build artifacts older than the threshold
+  quarantine [list | restore <ID> | purge [--all]]
+           Inspect, restore or purge quarantined artifacts
+  bench    Time a sequential and a parallel scan of a generated tree
+  help     Print this help
+
+Options:
+  -p, --path <DIR>         Directory to scan, repeat for several (default: enabled scan roots)
+  -o, --older-than <AGE>   Only include artifacts at least AGE old, e.g. 45, 6w, 18mo or 2y
+                           (default: saved threshold, 0 with --rules)
+      --max-age <AGE>      Leave out artifacts older than AGE (default: saved setting)
+      --min-size <SIZE>    Leave out artifacts smaller than SIZE on disk, e.g. 500k, 50MB or 1.5G
+                           (default: saved setting)
+  -y, --yes                Delete without asking for confirmation (clean only)
+  -n, --dry-run            Report what clean would remove without touching disk (clean only)
+      --trash              Move artifacts to the trash (clean only, Linux)
+      --quarantine         Move artifacts to the app's quarantine (clean only)
+      --permanent          Delete artifacts permanently (clean only)
+      --rules              Only delete artifacts a select rule in the config matches (clean only)
+      --all                Purge every quarantined artifact, not just expired ones
+  -f, --format <FORMAT>    Print a report as json, csv or ndjson instead of a table (scan only)
+      --output <FILE>      Write the report to FILE instead of stdout (scan only)
+  -j, --threads <N>        Worker threads for scanning, 0 for one per CPU (default: saved setting)
+      --no-index           Re-size every artifact instead of reusing sizes from the scan index
+  -x, --one-file-system    Stay on the filesystem of each scan path
+      --network-mounts     Also scan NFS, SMB, sshfs and other network mounts
+  -d, --max-depth <N>      Look at most N levels below each scan path (default: saved setting)
+  -L, --follow-symlinks    Enter symlinked directories, each directory at most once
+  -a, --age-by <SIGNAL>    Measure age from newest, sources, git, lockfile or artifact (default: saved setting)
+      --projects <N>       Projects in the generated tree (bench only, default 200)
+      --packages <N>       Packages per node_modules (bench only, default 20)
+      --files <N>          Files per package (bench only, default 10)
+      --rounds <N>         Timed scans per configuration (bench only, default 3)
+
+Exit codes:
+  0  success
+  1  one or more artifacts could not be deleted or restored, the saved protections could not be
+     read, or the report could not be written
+  2  invalid arguments
+  3  clean aborted at the confirmation prompt";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Scan,
+    Clean,
+    Quarantine(QuarantineAction),
+    Bench,
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+enum QuarantineAction {
+    List,
+    Restore(String),
+    Purge,
+}
+
+#[derive(Debug)]
+struct Options {
+    command: Command,
+    paths: Vec<PathBuf>,
+    older_than: u32,
+    max_age: Option<u32>,
+    min_size: Option<u64>,
+    yes: bool,
+    dry_run: bool,
+    delete_mode: DeleteMode,
+    format: Option<ReportFormat>,
+    output: Option<PathBuf>,
+    purge_all: bool,
+    quarantine_days: u32,
+    threads: usize,
+    use_index: bool,
+    one_filesystem: bool,
+    network_mounts: bool,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    activity_signal: ActivitySignal,
+    filter: PathFilter,
+    /// Compiled from `rules` in the config
+    rules: RuleSet,
+    /// Let select rules decide what clean removes
+    use_rules: bool,
+    bench: BenchOptions,
+}
+
+/// Returns true when the process was started with a headless subcommand
+pub fn is_headless(args: &[String]) -> bool {
+    matches!(
+        args.first().map(String::as_str),
+        Some(
+            "scan"
+                | "clean"
+                | "quarantine"
+                | "bench"
+                | "help"
+                | "--help"
+                | "-h"
+                | "--version"
+                | "-V"
+        )
+    )
+}
+
+/// Run a headless command and return the process exit code
+pub fn run(args: &[String]) -> i32 {
+    if matches!(args.first().map(String::as_str), Some("--version" | "-V")) {
+        println!("dev-storage-cleaner {}", env!("CARGO_PKG_VERSION"));
+        return EXIT_OK;
+    }
+
+    let options = match parse_args(args, &Config::load()) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+
+    match &options.command {
+        Command::Help => {
+            println!("{}", USAGE);
+            EXIT_OK
+        }
+        Command::Scan => {
+            let projects = scan(&options);
+            match options.format {
+                Some(format) => export(&projects, format, options.output.as_ref()),
+                None => {
+                    print_table(&projects);
+                    EXIT_OK
+                }
+            }
+        }
+        Command::Clean => clean(&options),
+        Command::Quarantine(action) => quarantine(action, &options),
+        Command::Bench => {
+            let bench = BenchOptions {
+                threads: options.threads,
+                ..options.bench.clone()
+            };
+            match bench::run(&bench) {
+                Ok(()) => EXIT_OK,
+                Err(e) => {
+                    eprintln!("error: benchmark failed: {}", e);
+                    EXIT_FAILED
+                }
+            }
+        }
+    }
+}
+
+fn parse_args(args: &[String], config: &Config) -> Result<Options, String> {
+    let mut iter = args.iter();
+    let command = match iter.next().map(String::as_str) {
+        Some("scan") => Command::Scan,
+        Some("clean") => Command::Clean,
+        Some("quarantine") => match iter.as_slice().first().map(String::as_str) {
+            Some("list") => {
+                iter.next();
+                Command::Quarantine(QuarantineAction::List)
+            }
+            Some("restore") => {
+                iter.next();
+                let id = iter.next().ok_or("quarantine restore needs an id")?;
+                Command::Quarantine(QuarantineAction::Restore(id.clone()))
+            }
+            Some("purge") => {
+                iter.next();
+                Command::Quarantine(QuarantineAction::Purge)
+            }
+            _ => Command::Quarantine(QuarantineAction::List),
+        },
+        Some("bench") => Command::Bench,
+        Some("help" | "--help" | "-h") => Command::Help,
+        Some(other) => return Err(format!("unknown command '{}'", other)),
+        None => return Err("missing command".to_string()),
+    };
+
+    let mut options = Options {
+        command,
+        paths: config.scan_roots(),
+        older_than: config.threshold_days,
+        max_age: config.max_age_days,
+        min_size: config.min_size_bytes,
+        yes: false,
+        dry_run: false,
+        delete_mode: config.delete_mode,
+        format: None,
+        output: None,
+        purge_all: false,
+        quarantine_days: config.quarantine_days,
+        threads: config.scan_threads,
+        use_index: true,
+        one_filesystem: config.one_filesystem,
+        network_mounts: config.scan_network_mounts,
+        max_depth: config.max_depth,
+        follow_symlinks: config.follow_symlinks,
+        activity_signal: config.activity_signal,
+        filter: PathFilter::default(),
+        rules: RuleSet::default(),
+        use_rules: false,
+        bench: BenchOptions::default(),
+    };
+
+    let mut paths = Vec::new();
+    let mut older_than = None;
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-p" | "--path" => {
+                let value = iter.next().ok_or("--path needs a directory")?;
+                paths.push(protect::expand_home(value));
+            }
+            "-o" | "--older-than" => {
+                let value = iter.next().ok_or("--older-than needs an age")?;
+                older_than = Some(filters::parse_days(value)?);
+            }
+            "--max-age" => {
+                let value = iter.next().ok_or("--max-age needs an age")?;
+                options.max_age = Some(filters::parse_days(value)?);
+            }
+            "--min-size" => {
+                let value = iter.next().ok_or("--min-size needs a size")?;
+                options.min_size = Some(filters::parse_size(value)?);
+            }
+            "-y" | "--yes" => options.yes = true,
+            "-n" | "--dry-run" => options.dry_run = true,
+            "--trash" => options.delete_mode = DeleteMode::Trash,
+            "--quarantine" => options.delete_mode = DeleteMode::Quarantine,
+            "--permanent" => options.delete_mode = DeleteMode::Permanent,
+            "--rules" => options.use_rules = true,
+            "--all" => options.purge_all = true,
+            "-f" | "--format" => {
+                let value = iter.next().ok_or("--format needs json, csv or ndjson")?;
+                options.format = Some(value.parse()?);
+            }
+            "--output" => {
+                let value = iter.next().ok_or("--output needs a file")?;
+                options.output = Some(protect::expand_home(value));
+            }
+            "-j" | "--threads" => options.threads = parse_number(iter.next(), arg)?,
+            "--no-index" => options.use_index = false,
+            "-x" | "--one-file-system" => options.one_filesystem = true,
+            "--network-mounts" => options.network_mounts = true,
+            "-d" | "--max-depth" => options.max_depth = Some(parse_number(iter.next(), arg)?),
+            "-L" | "--follow-symlinks" => options.follow_symlinks = true,
+            "-a" | "--age-by" => {
+                let value = iter.next().ok_or("--age-by needs a signal")?;
+                options.activity_signal = value.parse()?;
+            }
+            "--projects" => options.bench.projects = parse_number(iter.next(), arg)?,
+            "--packages" => options.bench.packages = parse_number(iter.next(), arg)?,
+            "--files" => options.bench.files = parse_number(iter.next(), arg)?,
+            "--rounds" => options.bench.rounds = parse_number(iter.next(), arg)?,
+            "-h" | "--help" => options.command = Command::Help,
+            other => return Err(format!("unknown option '{}'", other)),
+        }
+    }
+
+    if options.output.is_some() && options.format.is_none() {
+        options.format = options.output.as_deref().and_then(ReportFormat::from_path);
+        if options.format.is_none() {
+            return Err("cannot tell the report format from --output, pass --format".to_string());
+        }
+    }
+
+    if !paths.is_empty() {
+        options.paths = paths;
+    }
+    // Rules carry their own age conditions, so they see every artifact
+    options.older_than = match older_than {
+        Some(days) => days,
+        None if options.use_rules => 0,
+        None => config.threshold_days,
+    };
+    options.rules =
+        RuleSet::new(&config.rules).map_err(|e| format!("{} in rules of the config", e))?;
+    if options.use_rules && options.rules.is_empty() {
+        return Err("--rules needs at least one rule in the config".to_string());
+    }
+    // Anchored patterns are relative to the roots actually scanned
+    options.filter = PathFilter::new(&config.scan_patterns, options.paths.clone())
+        .map_err(|e| format!("{} in scan_patterns of the config", e))?;
+
+    if options.delete_mode == DeleteMode::Trash && !trash::is_supported() {
+        return Err("--trash is only supported on Linux, pass --permanent".to_string());
+    }
+
+    let needs_path = matches!(options.command, Command::Scan | Command::Clean);
+    if needs_path {
+        if options.paths.is_empty() {
+            return Err("no scan roots are enabled, pass --path".to_string());
+        }
+        if let Some(path) = options.paths.iter().find(|p| !p.is_dir()) {
+            return Err(format!("{} is not a directory", path.display()));
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_number<T: FromStr>(value: Option<&String>, flag: &str) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a number", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid number '{}' for {}", value, flag))
+}
+
+fn scan(options: &Options) -> Vec<ProjectInfo> {
+    let paths: Vec<String> = options
+        .paths
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect();
+    let mut limits = String::new();
+    if let Some(days) = options.max_age {
+        limits.push_str(&format!(", at most {} old", filters::format_days(days)));
+    }
+    if let Some(bytes) = options.min_size {
+        limits.push_str(&format!(
+            ", taking at least {}",
+            filters::format_size(bytes)
+        ));
+    }
+    eprintln!(
+        "Scanning {} for artifacts at least {} day(s) old{}...",
+        paths.join(", "),
+        options.older_than,
+        limits
+    );
+
+    let mut scanner = Scanner::new(options.older_than)
+        .with_threads(options.threads)
+        .with_filter(options.filter.clone())
+        .with_one_filesystem(options.one_filesystem)
+        .with_network_mounts(options.network_mounts)
+        .with_max_depth(options.max_depth)
+        .with_follow_symlinks(options.follow_symlinks)
+        .with_activity_signal(options.activity_signal);
+    if options.use_index {
+        scanner = scanner.with_index(ScanIndex::load());
+    }
+    let mut projects: Vec<ProjectInfo> = options
+        .paths
+        .iter()
+        .flat_map(|path| scanner.scan(path))
+        .collect();
+    let filter = ResultFilter {
+        min_age_days: options.older_than,
+        max_age_days: options.max_age,
+        min_size_bytes: options.min_size,
+    };
+    projects.retain(|p| filter.matches(p));
+    projects.sort_by_key(|p| std::cmp::Reverse(p.disk_bytes));
+    if !options.rules.is_empty() {
+        for project in projects.iter_mut() {
+            project.rule = options.rules.evaluate(project);
+        }
+        let found = projects.len();
+        projects.retain(|p| p.rule_action() != Some(RuleAction::Ignore));
+        if projects.len() < found {
+            eprintln!(
+                "Left out {} artifact(s) matched by ignore rules",
+                found - projects.len()
+            );
+        }
+    }
+
+    for skipped in scanner.skipped_mounts() {
+        eprintln!("Skipped {}", skipped);
+    }
+    let errors = scanner.errors();
+    for error in errors.iter().take(MAX_ERRORS_SHOWN) {
+        eprintln!("Error: {}", error);
+    }
+    if errors.len() > MAX_ERRORS_SHOWN {
+        eprintln!("... and {} more", errors.len() - MAX_ERRORS_SHOWN);
+    }
+    // A broken ignore file is skipped, which hides nothing
+    let unreadable = errors
+        .iter()
+        .filter(|error| error.kind != ScanErrorKind::InvalidIgnoreFile)
+        .count();
+    if unreadable > 0 {
+        eprintln!(
+            "{} path(s) could not be read; artifacts below them are missing and sizes may be too small",
+            unreadable
+        );
+    }
+    if let Some(Err(e)) = scanner.into_index().map(|index| index.save()) {
+        eprintln!("Failed to save scan index: {}", e);
+    }
+    projects
+}
+
+fn clean(options: &Options) -> i32 {
+    let mut projects = scan(options);
+    print_table(&projects);
+
+    let protect = match ProtectList::load() {
+        Ok(protect) => protect,
+        Err(e) => {
+            eprintln!("Error: {}, nothing was deleted", e);
+            return EXIT_FAILED;
+        }
+    };
+    let found = projects.len();
+    projects.retain(|project| {
+        !protect.protects(project) && project.rule_action() != Some(RuleAction::Protect)
+    });
+    if projects.len() < found {
+        eprintln!(
+            "Leaving {} protected project(s) alone",
+            found - projects.len()
+        );
+    }
+    if options.use_rules {
+        projects.retain(|p| p.rule_action() == Some(RuleAction::Select));
+        eprintln!("{} artifact(s) matched by select rules", projects.len());
+    }
+    if projects.is_empty() {
+        if !options.dry_run {
+            purge_expired(options);
+        }
+        return EXIT_OK;
+    }
+
+    for project in &projects {
+        if let Some(git) = project.git.as_ref().filter(|git| git.has_local_work()) {
+            eprintln!(
+                "Warning: {} has work in progress ({})",
+                project.project_path.display(),
+                git.summary()
+            );
+        }
+    }
+
+    if options.dry_run {
+        for project in projects.iter_mut() {
+            project.selected = true;
+        }
+        let report = preview::preview_selected(&projects, options.delete_mode);
+        print_preview(&report);
+        return if report.refused.is_some() {
+            EXIT_FAILED
+        } else {
+            EXIT_OK
+        };
+    }
+
+    let total_gb: f64 = projects.iter().map(|p| p.size_gb()).sum();
+    let action = match options.delete_mode {
+        DeleteMode::Trash => "Move to trash",
+        DeleteMode::Quarantine => "Quarantine",
+        DeleteMode::Permanent => "Permanently delete",
+    };
+    let prompt = format!(
+        "{} {} artifact(s), {:.2} GB? [y/N] ",
+        action,
+        projects.len(),
+        total_gb
+    );
+    if !options.yes && !confirm(&prompt) {
+        eprintln!("Aborted, nothing was deleted");
+        return EXIT_ABORTED;
+    }
+
+    for project in projects.iter_mut() {
+        project.selected = true;
+    }
+    let summary = cleaner::delete_selected(&mut projects, options.delete_mode, &CancelToken::new());
+    println!("{}", summary.status_message());
+    purge_expired(options);
+
+    if summary.failed_count > 0 || summary.refused.is_some() {
+        EXIT_FAILED
+    } else {
+        EXIT_OK
+    }
+}
+
+/// Purge quarantine entries past their time, like the app does on start.
+/// Only commands that are allowed to change disk call this.
+fn purge_expired(options: &Options) {
+    let (purged, _) = Quarantine::open().purge_older_than(options.quarantine_days);
+    if purged > 0 {
+        eprintln!("Purged {} expired quarantine item(s)", purged);
+    }
+}
+
+fn quarantine(action: &QuarantineAction, options: &Options) -> i32 {
+    let mut quarantine = Quarantine::open();
+
+    match action {
+        QuarantineAction::List => {
+            if quarantine.entries().is_empty() {
+                println!("Quarantine is empty");
+                return EXIT_OK;
+            }
+            println!(
+                "{:>10}  {:>9}  {:<40}  ORIGINAL PATH",
+                "SIZE", "PURGED IN", "ID"
+            );
+            for entry in quarantine.entries() {
+                let days_left =
+                    (options.quarantine_days as u64).saturating_sub(entry.days_in_quarantine());
+                println!(
+                    "{:>7.2} GB  {:>8}d  {:<40}  {}",
+                    entry.size_gb(),
+                    days_left,
+                    entry.id,
+                    entry.original_path.display()
+                );
+            }
+            EXIT_OK
+        }
+        QuarantineAction::Restore(id) => match quarantine.restore(id) {
+            Ok(path) => {
+                println!("✅ Restored {}", path.display());
+                EXIT_OK
+            }
+            Err(e) => {
+                eprintln!("error: failed to restore {}: {}", id, e);
+                EXIT_FAILED
+            }
+        },
+        QuarantineAction::Purge => {
+            let days = if options.purge_all {
+                0
+            } else {
+                options.quarantine_days
+            };
+            let (purged, purged_bytes) = quarantine.purge_older_than(days);
+            println!(
+                "✅ Purged {} quarantined artifact(s) - Freed {:.2} GB",
+                purged,
+                purged_bytes as f64 / (1024.0 * 1024.0 * 1024.0)
+            );
+            if options.purge_all && !quarantine.entries().is_empty() {
+                EXIT_FAILED
+            } else {
+                EXIT_OK
+            }
+        }
+    }
+}
+
+fn export(projects: &[ProjectInfo], format: ReportFormat, output: Option<&PathBuf>) -> i32 {
+    let result = match output {
+        Some(path) => File::create(path)
+            .and_then(|file| report::write_report(projects, format, BufWriter::new(file))),
+        None => report::write_report(projects, format, io::stdout().lock()),
+    };
+
+    match result {
+        Ok(()) => {
+            if let Some(path) = output {
+                eprintln!("Wrote {} report to {}", format, path.display());
+            }
+            EXIT_OK
+        }
+        Err(e) => {
+            eprintln!("error: failed to write report: {}", e);
+            EXIT_FAILED
+        }
+    }
+}
+
+fn print_preview(report: &DryRunReport) {
+    println!();
+    for artifact in &report.artifacts {
+        println!(
+            "{:>7.2} GB  {:>8} files  {}",
+            artifact.total_bytes as f64 / (1024.0 * 1024.0 * 1024.0),
+            artifact.file_count,
+            artifact.artifact_path.display()
+        );
+        for finding in &artifact.findings {
+            println!("    ! {}", finding);
+        }
+        if artifact.omitted_findings > 0 {
+            println!("    ! ... and {} more", artifact.omitted_findings);
+        }
+    }
+    println!("{}", report.status_message());
+}
+
+fn confirm(prompt: &str) -> bool {
+    eprint!("{}", prompt);
+    io::stderr().flush().ok();
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim(), "y" | "Y" | "yes" | "YES")
+}
+
+fn print_table(projects: &[ProjectInfo]) {
+    if projects.is_empty() {
+        println!("No build artifacts found");
+        return;
+    }
+
+    println!(
+        "{:>10}  {:>10}  {:>6}  {:<13}  PATH",
+        "ON DISK", "APPARENT", "AGE", "KIND"
+    );
+    for project in projects {
+        let rule = project
+            .rule
+            .as_ref()
+            .map(|rule| format!("  ({}: {})", rule.action, rule.name))
+            .unwrap_or_default();
+        println!(
+            "{:>7.2} GB  {:>7.2} GB  {:>5}d  {:<13}  {}{}",
+            project.size_gb(),
+            project.apparent_gb(),
+            project.days_old(),
+            project.kind.label(),
+            project.artifact_path.display(),
+            rule
+        );
+    }
+
+    let total_gb: f64 = projects.iter().map(|p| p.size_gb()).sum();
+    println!(
+        "{:>7.2} GB  total in {} artifact(s)",
+        total_gb,
+        projects.len()
+    );
+}
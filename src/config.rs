@@ -1,45 +1,156 @@
+use crate::activity::ActivitySignal;
+use crate::cleaner::DeleteMode;
+use crate::filters::ResultFilter;
+use crate::listing::{GroupBy, ListOrder, SortKey};
+use crate::patterns::{self, PathFilter};
+use crate::rules::Rule;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// A directory the scanner walks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanRoot {
+    pub path: PathBuf,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    pub scan_path: PathBuf,
+    #[serde(default)]
+    pub roots: Vec<ScanRoot>,
+    /// The single scan directory of older config files, moved into `roots` on load
+    #[serde(default, skip_serializing)]
+    scan_path: Option<PathBuf>,
     pub threshold_days: u32,
+    /// Hide artifacts of projects idle for longer than this
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    /// Hide artifacts taking less than this on disk
+    #[serde(default)]
+    pub min_size_bytes: Option<u64>,
+    #[serde(default)]
+    pub delete_mode: DeleteMode,
+    /// Quarantined artifacts older than this are purged for good
+    #[serde(default = "default_quarantine_days")]
+    pub quarantine_days: u32,
+    /// Worker threads for scanning, 0 for one per CPU
+    #[serde(default)]
+    pub scan_threads: usize,
+    /// Gitignore-style patterns for directories the scanner skips, see `patterns`
+    #[serde(default = "patterns::default_patterns")]
+    pub scan_patterns: Vec<String>,
+    /// Stay on the filesystem of each scan root
+    #[serde(default)]
+    pub one_filesystem: bool,
+    /// Walk into NFS, SMB, sshfs and other network mounts
+    #[serde(default)]
+    pub scan_network_mounts: bool,
+    /// Levels below each scan root to look for artifacts, unlimited when unset
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    /// Enter symlinked directories, each one only once
+    #[serde(default)]
+    pub follow_symlinks: bool,
+    /// What a project's age, and so the threshold, is measured from
+    #[serde(default)]
+    pub activity_signal: ActivitySignal,
+    /// Paths and globs of projects that are never cleaned, see `protect`
+    #[serde(default)]
+    pub protected: Vec<String>,
+    /// Cleanup rules, tried in order, see `rules`
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// What the project list is sorted by
+    #[serde(default)]
+    pub sort_key: SortKey,
+    /// Sort smallest, newest and A first instead
+    #[serde(default)]
+    pub sort_ascending: bool,
+    #[serde(default)]
+    pub group_by: GroupBy,
+}
+
+fn default_quarantine_days() -> u32 {
+    14
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            scan_path: dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")),
+            roots: vec![ScanRoot {
+                path: dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")),
+                enabled: true,
+            }],
+            scan_path: None,
             threshold_days: 30,
+            max_age_days: None,
+            min_size_bytes: None,
+            delete_mode: DeleteMode::default(),
+            quarantine_days: default_quarantine_days(),
+            scan_threads: 0,
+            scan_patterns: patterns::default_patterns(),
+            one_filesystem: false,
+            scan_network_mounts: false,
+            max_depth: None,
+            follow_symlinks: false,
+            activity_signal: ActivitySignal::default(),
+            protected: Vec::new(),
+            rules: Vec::new(),
+            sort_key: SortKey::default(),
+            sort_ascending: false,
+            group_by: GroupBy::default(),
         }
     }
 }
 
 impl Config {
-    fn config_path() -> PathBuf {
+    /// Directory holding `config.json` and the app's other state
+    pub fn config_dir() -> PathBuf {
         if let Some(config_dir) = dirs::config_dir() {
             let app_config_dir = config_dir.join("dev-storage-cleaner");
             fs::create_dir_all(&app_config_dir).ok();
-            app_config_dir.join("config.json")
+            app_config_dir
         } else {
-            PathBuf::from("config.json")
+            PathBuf::from(".")
         }
     }
 
+    fn config_path() -> PathBuf {
+        Self::config_dir().join("config.json")
+    }
+
+    /// The saved config, or the defaults when it is missing or broken
     pub fn load() -> Self {
+        Self::try_load().unwrap_or_default()
+    }
+
+    /// The saved config, the defaults when there is none, and an error when
+    /// it cannot be read or parsed
+    pub fn try_load() -> Result<Self, String> {
         let config_path = Self::config_path();
+        if !config_path.exists() {
+            return Ok(Self::default());
+        }
 
-        if config_path.exists() {
-            if let Ok(contents) = fs::read_to_string(&config_path) {
-                if let Ok(config) = serde_json::from_str(&contents) {
-                    return config;
-                }
+        let contents = fs::read_to_string(&config_path)
+            .map_err(|e| format!("cannot read {}: {}", config_path.display(), e))?;
+        let mut config = serde_json::from_str::<Config>(&contents)
+            .map_err(|e| format!("cannot parse {}: {}", config_path.display(), e))?;
+        if let Some(path) = config.scan_path.take() {
+            if config.roots.is_empty() {
+                config.roots.push(ScanRoot {
+                    path,
+                    enabled: true,
+                });
             }
         }
-
-        Self::default()
+        Ok(config)
     }
 
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
@@ -48,4 +159,67 @@ impl Config {
         fs::write(&config_path, contents)?;
         Ok(())
     }
+
+    /// Enabled roots, leaving out any inside another enabled root so nothing
+    /// is scanned twice
+    pub fn scan_roots(&self) -> Vec<PathBuf> {
+        let enabled: Vec<&Path> = self
+            .roots
+            .iter()
+            .filter(|r| r.enabled)
+            .map(|r| r.path.as_path())
+            .collect();
+        let mut roots: Vec<PathBuf> = Vec::new();
+        for path in &enabled {
+            let nested = enabled
+                .iter()
+                .any(|other| other != path && path.starts_with(other));
+            if !nested && !roots.iter().any(|r| r == path) {
+                roots.push(path.to_path_buf());
+            }
+        }
+        roots
+    }
+
+    /// The saved threshold, age limit and size limit
+    pub fn result_filter(&self) -> ResultFilter {
+        ResultFilter {
+            min_age_days: self.threshold_days,
+            max_age_days: self.max_age_days,
+            min_size_bytes: self.min_size_bytes,
+        }
+    }
+
+    /// The saved sort order and grouping of the project list
+    pub fn list_order(&self) -> ListOrder {
+        ListOrder {
+            key: self.sort_key,
+            ascending: self.sort_ascending,
+            group_by: self.group_by,
+        }
+    }
+
+    /// Exclusion patterns compiled against the enabled roots
+    pub fn path_filter(&self) -> Result<PathFilter, String> {
+        PathFilter::new(&self.scan_patterns, self.scan_roots())
+    }
+
+    /// The enabled root an artifact was found under
+    pub fn root_of(&self, path: &Path) -> Option<PathBuf> {
+        self.scan_roots()
+            .into_iter()
+            .find(|root| path.starts_with(root))
+    }
+
+    /// Add `path` as an enabled root. Returns false if it is already listed.
+    pub fn add_root(&mut self, path: PathBuf) -> bool {
+        if self.roots.iter().any(|r| r.path == path) {
+            return false;
+        }
+        self.roots.push(ScanRoot {
+            path,
+            enabled: true,
+        });
+        true
+    }
 }
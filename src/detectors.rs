@@ -0,0 +1,350 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// The kind of build artifact a detector recognised.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    NodeModules,
+    NextBuild,
+    NuxtBuild,
+    Dist,
+    CargoTarget,
+    MavenTarget,
+    GradleBuild,
+    GradleCache,
+    PythonVenv,
+    PythonCache,
+}
+
+impl ArtifactKind {
+    /// Stable lowercase identifier used in reports
+    pub fn id(&self) -> &'static str {
+        match self {
+            ArtifactKind::NodeModules => "node_modules",
+            ArtifactKind::NextBuild => "next",
+            ArtifactKind::NuxtBuild => "nuxt",
+            ArtifactKind::Dist => "dist",
+            ArtifactKind::CargoTarget => "cargo_target",
+            ArtifactKind::MavenTarget => "maven_target",
+            ArtifactKind::GradleBuild => "gradle_build",
+            ArtifactKind::GradleCache => "gradle_cache",
+            ArtifactKind::PythonVenv => "python_venv",
+            ArtifactKind::PythonCache => "python_cache",
+        }
+    }
+
+    /// Short uppercase label used on project cards
+    pub fn label(&self) -> &'static str {
+        match self {
+            ArtifactKind::NodeModules => "NODE_MODULES",
+            ArtifactKind::NextBuild => "NEXT",
+            ArtifactKind::NuxtBuild => "NUXT",
+            ArtifactKind::Dist => "DIST",
+            ArtifactKind::CargoTarget => "CARGO TARGET",
+            ArtifactKind::MavenTarget => "MAVEN TARGET",
+            ArtifactKind::GradleBuild => "GRADLE BUILD",
+            ArtifactKind::GradleCache => "GRADLE CACHE",
+            ArtifactKind::PythonVenv => "VENV",
+            ArtifactKind::PythonCache => "PYCACHE",
+        }
+    }
+}
+
+/// A confirmed artifact directory together with the marker file that proved it.
+#[derive(Clone, Debug)]
+pub struct Detection {
+    pub kind: ArtifactKind,
+    /// File name of the marker, e.g. `package.json` or `pyvenv.cfg`
+    pub marker: String,
+}
+
+/// Recognises build artifact directories for one ecosystem.
+///
+/// The scanner calls `detect` for every directory it visits. A directory only
+/// counts as an artifact when a marker file confirms it, so a bare `target/`
+/// or `build/` next to no manifest is left alone and descended into as usual.
+/// A detected directory is reported and never descended into.
+pub trait ArtifactDetector: Send + Sync {
+    fn detect(&self, path: &Path) -> Option<Detection>;
+}
+
+fn dir_name(path: &Path) -> Option<&str> {
+    path.file_name().and_then(|n| n.to_str())
+}
+
+/// Confirm `path` as `kind` if its parent directory contains one of `markers`
+fn sibling_marker(path: &Path, kind: ArtifactKind, markers: &[&str]) -> Option<Detection> {
+    let parent = path.parent()?;
+    markers
+        .iter()
+        .find(|marker| parent.join(marker).is_file())
+        .map(|marker| Detection {
+            kind,
+            marker: marker.to_string(),
+        })
+}
+
+/// `node_modules`, `.next`, `.nuxt` and `dist`
+pub struct NodeDetector;
+
+impl ArtifactDetector for NodeDetector {
+    fn detect(&self, path: &Path) -> Option<Detection> {
+        match dir_name(path)? {
+            "node_modules" => sibling_marker(path, ArtifactKind::NodeModules, &["package.json"]),
+            ".next" => sibling_marker(
+                path,
+                ArtifactKind::NextBuild,
+                &[
+                    "next.config.js",
+                    "next.config.mjs",
+                    "next.config.ts",
+                    "package.json",
+                ],
+            ),
+            ".nuxt" => sibling_marker(
+                path,
+                ArtifactKind::NuxtBuild,
+                &["nuxt.config.ts", "nuxt.config.js", "package.json"],
+            ),
+            "dist" => sibling_marker(path, ArtifactKind::Dist, &["package.json"]),
+            _ => None,
+        }
+    }
+}
+
+/// Cargo `target`
+pub struct RustDetector;
+
+impl ArtifactDetector for RustDetector {
+    fn detect(&self, path: &Path) -> Option<Detection> {
+        match dir_name(path)? {
+            "target" => sibling_marker(path, ArtifactKind::CargoTarget, &["Cargo.toml"]),
+            _ => None,
+        }
+    }
+}
+
+/// Maven `target`
+pub struct MavenDetector;
+
+impl ArtifactDetector for MavenDetector {
+    fn detect(&self, path: &Path) -> Option<Detection> {
+        match dir_name(path)? {
+            "target" => sibling_marker(path, ArtifactKind::MavenTarget, &["pom.xml"]),
+            _ => None,
+        }
+    }
+}
+
+const GRADLE_MARKERS: &[&str] = &[
+    "build.gradle",
+    "build.gradle.kts",
+    "settings.gradle",
+    "settings.gradle.kts",
+];
+
+/// Gradle `build` and `.gradle`
+pub struct GradleDetector;
+
+impl ArtifactDetector for GradleDetector {
+    fn detect(&self, path: &Path) -> Option<Detection> {
+        match dir_name(path)? {
+            "build" => sibling_marker(path, ArtifactKind::GradleBuild, GRADLE_MARKERS),
+            ".gradle" => sibling_marker(path, ArtifactKind::GradleCache, GRADLE_MARKERS),
+            _ => None,
+        }
+    }
+}
+
+/// `.venv`, `venv` and `__pycache__`
+pub struct PythonDetector;
+
+impl ArtifactDetector for PythonDetector {
+    fn detect(&self, path: &Path) -> Option<Detection> {
+        match dir_name(path)? {
+            // Every virtualenv carries its own pyvenv.cfg, which is a stronger
+            // signal than any project manifest next to it
+            ".venv" | "venv" => {
+                if path.join("pyvenv.cfg").is_file() {
+                    Some(Detection {
+                        kind: ArtifactKind::PythonVenv,
+                        marker: "pyvenv.cfg".to_string(),
+                    })
+                } else {
+                    sibling_marker(
+                        path,
+                        ArtifactKind::PythonVenv,
+                        &["pyproject.toml", "requirements.txt", "setup.py"],
+                    )
+                }
+            }
+            // __pycache__ is only genuine next to the sources it caches
+            "__pycache__" => {
+                let parent = path.parent()?;
+                fs::read_dir(parent)
+                    .ok()?
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.file_name().to_string_lossy().into_owned())
+                    .find(|name| name.ends_with(".py"))
+                    .map(|marker| Detection {
+                        kind: ArtifactKind::PythonCache,
+                        marker,
+                    })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// All built-in detectors. The first detector that matches wins.
+pub fn builtin() -> Vec<Box<dyn ArtifactDetector>> {
+    vec![
+        Box::new(NodeDetector),
+        Box::new(RustDetector),
+        Box::new(MavenDetector),
+        Box::new(GradleDetector),
+        Box::new(PythonDetector),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A project directory holding `files`, with an empty `artifact` folder
+    fn project(name: &str, files: &[&str], artifact: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("dsc-detectors-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(artifact)).unwrap();
+        for file in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        dir.join(artifact)
+    }
+
+    /// Test name, files in the project, artifact folder, expected detection
+    type Case = (
+        &'static str,
+        &'static [&'static str],
+        &'static str,
+        Option<(ArtifactKind, &'static str)>,
+    );
+
+    fn detect(path: &Path) -> Option<(ArtifactKind, String)> {
+        builtin()
+            .iter()
+            .find_map(|d| d.detect(path))
+            .map(|detection| (detection.kind, detection.marker))
+    }
+
+    #[test]
+    fn confirms_artifacts_by_sibling_marker() {
+        use ArtifactKind::*;
+        let cases: &[Case] = &[
+            (
+                "node",
+                &["package.json"],
+                "node_modules",
+                Some((NodeModules, "package.json")),
+            ),
+            ("node-bare", &[], "node_modules", None),
+            (
+                "next",
+                &["next.config.mjs", "package.json"],
+                ".next",
+                Some((NextBuild, "next.config.mjs")),
+            ),
+            (
+                "next-pkg",
+                &["package.json"],
+                ".next",
+                Some((NextBuild, "package.json")),
+            ),
+            (
+                "nuxt",
+                &["nuxt.config.ts"],
+                ".nuxt",
+                Some((NuxtBuild, "nuxt.config.ts")),
+            ),
+            (
+                "dist",
+                &["package.json"],
+                "dist",
+                Some((Dist, "package.json")),
+            ),
+            ("dist-bare", &["setup.py"], "dist", None),
+            (
+                "cargo",
+                &["Cargo.toml"],
+                "target",
+                Some((CargoTarget, "Cargo.toml")),
+            ),
+            (
+                "maven",
+                &["pom.xml"],
+                "target",
+                Some((MavenTarget, "pom.xml")),
+            ),
+            // Cargo is tried before Maven
+            (
+                "both",
+                &["pom.xml", "Cargo.toml"],
+                "target",
+                Some((CargoTarget, "Cargo.toml")),
+            ),
+            ("target-bare", &["Makefile"], "target", None),
+            (
+                "gradle",
+                &["build.gradle.kts"],
+                "build",
+                Some((GradleBuild, "build.gradle.kts")),
+            ),
+            (
+                "gradle-cache",
+                &["settings.gradle"],
+                ".gradle",
+                Some((GradleCache, "settings.gradle")),
+            ),
+            ("build-bare", &["package.json"], "build", None),
+            (
+                "venv-cfg",
+                &["venv/pyvenv.cfg"],
+                "venv",
+                Some((PythonVenv, "pyvenv.cfg")),
+            ),
+            (
+                "venv",
+                &["pyproject.toml"],
+                ".venv",
+                Some((PythonVenv, "pyproject.toml")),
+            ),
+            ("venv-bare", &[], ".venv", None),
+            (
+                "pycache",
+                &["main.py"],
+                "__pycache__",
+                Some((PythonCache, "main.py")),
+            ),
+            ("pycache-bare", &["README.md"], "__pycache__", None),
+            ("other", &["package.json"], "src", None),
+        ];
+        for (name, files, artifact, expected) in cases {
+            let path = project(name, files, artifact);
+            let expected = expected.map(|(kind, marker)| (kind, marker.to_string()));
+            assert_eq!(detect(&path), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn marker_must_be_a_file() {
+        let path = project("marker-dir", &[], "node_modules");
+        fs::create_dir_all(path.parent().unwrap().join("package.json")).unwrap();
+        assert_eq!(detect(&path), None);
+    }
+}
@@ -0,0 +1,107 @@
+//! Problems met while walking and sizing.
+//!
+//! A directory the scanner cannot read hides every artifact below it, and a
+//! file it cannot stat makes a size come out too small. These are collected
+//! instead of dropped so a scan can say it did not see everything.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScanErrorKind {
+    PermissionDenied,
+    /// Any other I/O failure
+    Io,
+    /// A `.devstorageignore` with a pattern that does not compile
+    InvalidIgnoreFile,
+}
+
+impl ScanErrorKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ScanErrorKind::PermissionDenied => "PERMISSION DENIED",
+            ScanErrorKind::Io => "I/O ERROR",
+            ScanErrorKind::InvalidIgnoreFile => "INVALID IGNORE FILE",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ScanError {
+    pub path: PathBuf,
+    pub kind: ScanErrorKind,
+    pub message: String,
+}
+
+impl ScanError {
+    /// `action` says what was being done, e.g. "cannot read directory"
+    pub fn new(path: &Path, action: &str, error: &io::Error) -> Self {
+        let kind = match error.kind() {
+            io::ErrorKind::PermissionDenied => ScanErrorKind::PermissionDenied,
+            _ => ScanErrorKind::Io,
+        };
+        Self {
+            path: path.to_path_buf(),
+            kind,
+            message: format!("{}: {}", action, error),
+        }
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+/// Errors collected by parallel walkers
+#[derive(Debug, Default)]
+pub struct ErrorLog {
+    errors: Mutex<Vec<ScanError>>,
+}
+
+impl ErrorLog {
+    /// Note that `action` failed on `path`. Entries deleted while the walk
+    /// was running are not errors, so `NotFound` is dropped.
+    pub fn record(&self, path: &Path, action: &str, error: &io::Error) {
+        if error.kind() == io::ErrorKind::NotFound {
+            return;
+        }
+        self.extend(vec![ScanError::new(path, action, error)]);
+    }
+
+    /// Note that the ignore file at `path` was skipped. Every walk below it
+    /// meets it again, so it is only recorded once.
+    pub fn record_invalid_ignore_file(&self, path: &Path, message: String) {
+        if let Ok(mut list) = self.errors.lock() {
+            let kind = ScanErrorKind::InvalidIgnoreFile;
+            if !list.iter().any(|e| e.kind == kind && e.path == path) {
+                list.push(ScanError {
+                    path: path.to_path_buf(),
+                    kind,
+                    message,
+                });
+            }
+        }
+    }
+
+    pub fn extend(&self, errors: Vec<ScanError>) {
+        if let Ok(mut list) = self.errors.lock() {
+            list.extend(errors);
+        }
+    }
+
+    /// Everything recorded so far
+    pub fn snapshot(&self) -> Vec<ScanError> {
+        self.errors
+            .lock()
+            .map(|errors| errors.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn into_errors(self) -> Vec<ScanError> {
+        self.errors.into_inner().unwrap_or_default()
+    }
+}
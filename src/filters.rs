@@ -0,0 +1,236 @@
+//! Age and size limits on the listed artifacts.
+//!
+//! Ages are written like `45`, `6w`, `18mo`, `2y` or `1y6mo`, a bare number
+//! being days; a month counts 30 days and a year 365. Sizes are written like
+//! `50MB`, `1.5G` or `500k`, in binary units, a bare number being bytes.
+
+use crate::scanner::ProjectInfo;
+
+const DAY_UNITS: &[(&str, u32)] = &[
+    ("d", 1),
+    ("day", 1),
+    ("days", 1),
+    ("w", 7),
+    ("week", 7),
+    ("weeks", 7),
+    ("mo", 30),
+    ("month", 30),
+    ("months", 30),
+    ("y", 365),
+    ("yr", 365),
+    ("year", 365),
+    ("years", 365),
+];
+
+const SIZE_UNITS: &[(&str, u64)] = &[
+    ("b", 1),
+    ("k", 1 << 10),
+    ("kb", 1 << 10),
+    ("kib", 1 << 10),
+    ("m", 1 << 20),
+    ("mb", 1 << 20),
+    ("mib", 1 << 20),
+    ("g", 1 << 30),
+    ("gb", 1 << 30),
+    ("gib", 1 << 30),
+    ("t", 1 << 40),
+    ("tb", 1 << 40),
+    ("tib", 1 << 40),
+];
+
+/// Days in an age like `6w` or `1y6mo`
+pub fn parse_days(input: &str) -> Result<u32, String> {
+    let invalid = || format!("invalid age '{}', use e.g. 45, 6w, 18mo or 2y", input);
+    let text = input.trim().to_ascii_lowercase();
+    if text.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut days: u32 = 0;
+    let mut rest = text.as_str();
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return Err(invalid());
+        }
+        let number: u32 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = rest[digits..].trim_start();
+        let letters = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let factor = match &rest[..letters] {
+            // A bare number only stands alone
+            "" if days == 0 && rest.is_empty() => 1,
+            unit => DAY_UNITS
+                .iter()
+                .find(|(name, _)| *name == unit)
+                .map(|(_, factor)| *factor)
+                .ok_or_else(invalid)?,
+        };
+        days = number
+            .checked_mul(factor)
+            .and_then(|part| days.checked_add(part))
+            .ok_or_else(|| format!("age '{}' is too large", input))?;
+        rest = rest[letters..].trim_start();
+    }
+    Ok(days)
+}
+
+/// The shortest way to write `days`, e.g. `6w` for 42 and `18mo` for 540
+pub fn format_days(days: u32) -> String {
+    match days {
+        0 => "0d".to_string(),
+        d if d % 365 == 0 => format!("{}y", d / 365),
+        d if d % 30 == 0 => format!("{}mo", d / 30),
+        d if d % 7 == 0 => format!("{}w", d / 7),
+        d => format!("{}d", d),
+    }
+}
+
+/// Bytes in a size like `50MB` or `1.5G`
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid size '{}', use e.g. 500k, 50MB or 1.5G", input);
+    let text = input.trim().to_ascii_lowercase();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let number: f64 = text[..split].parse().map_err(|_| invalid())?;
+    let factor = match text[split..].trim() {
+        "" => 1,
+        unit => SIZE_UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, factor)| *factor)
+            .ok_or_else(invalid)?,
+    };
+    let bytes = number * factor as f64;
+    if !bytes.is_finite() || bytes >= u64::MAX as f64 {
+        return Err(format!("size '{}' is too large", input));
+    }
+    Ok(bytes.round() as u64)
+}
+
+/// A size in the largest unit it has at least one of, e.g. `50MB`
+pub fn format_size(bytes: u64) -> String {
+    let (name, factor) = [
+        ("TB", 1u64 << 40),
+        ("GB", 1 << 30),
+        ("MB", 1 << 20),
+        ("KB", 1 << 10),
+    ]
+    .into_iter()
+    .find(|(_, factor)| bytes >= *factor)
+    .unwrap_or(("B", 1));
+    let value = bytes as f64 / factor as f64;
+    if value.fract() == 0.0 {
+        format!("{}{}", value, name)
+    } else {
+        format!("{:.1}{}", value, name)
+    }
+}
+
+/// Which artifacts are listed, by their project's age and their size on disk
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResultFilter {
+    pub min_age_days: u32,
+    pub max_age_days: Option<u32>,
+    pub min_size_bytes: Option<u64>,
+}
+
+impl ResultFilter {
+    pub fn matches(&self, project: &ProjectInfo) -> bool {
+        let days = project.days_old();
+        days >= self.min_age_days as u64
+            && self.max_age_days.is_none_or(|max| days <= max as u64)
+            && self
+                .min_size_bytes
+                .is_none_or(|min| project.disk_bytes >= min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ages() {
+        for (input, days) in [
+            ("45", 45),
+            ("0", 0),
+            ("6w", 42),
+            ("18mo", 540),
+            ("2y", 730),
+            ("1y6mo", 545),
+            ("1y 6mo", 545),
+            ("3 Days", 3),
+            (" 2W ", 14),
+        ] {
+            assert_eq!(parse_days(input), Ok(days), "{}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_ages() {
+        for input in [
+            "", "  ", "w", "6x", "1.5y", "6w3", "y6", "-5", "-2w", "6w-1d",
+        ] {
+            assert!(parse_days(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_ages_that_overflow() {
+        for input in ["4294967296", "20000000y", "4294967295d 1d"] {
+            assert!(parse_days(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn formats_ages_back() {
+        for days in [0, 1, 42, 540, 730, 545] {
+            assert_eq!(parse_days(&format_days(days)), Ok(days));
+        }
+        assert_eq!(format_days(540), "18mo");
+        assert_eq!(format_days(42), "6w");
+    }
+
+    #[test]
+    fn parses_sizes() {
+        for (input, bytes) in [
+            ("0", 0),
+            ("512", 512),
+            ("500k", 500 << 10),
+            ("50MB", 50 << 20),
+            ("50 mib", 50 << 20),
+            ("1.5G", 3 << 29),
+            ("2TB", 2 << 40),
+            ("10b", 10),
+        ] {
+            assert_eq!(parse_size(input), Ok(bytes), "{}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_sizes() {
+        for input in ["", "MB", "50XB", "1.5.5G", "five", "-1", "-50MB", "1e3"] {
+            assert!(parse_size(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_sizes_that_overflow() {
+        for input in ["16777216T", "99999999999999999999"] {
+            assert!(parse_size(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn formats_sizes() {
+        assert_eq!(format_size(50 << 20), "50MB");
+        assert_eq!(format_size(3 << 29), "1.5GB");
+        assert_eq!(format_size(100), "100B");
+        assert_eq!(parse_size(&format_size(3 << 29)), Ok(3 << 29));
+    }
+}
@@ -0,0 +1,743 @@
+//! Facts about a project's git repository, read straight from `.git`
+//! without running git.
+//!
+//! Deleting an artifact never touches committed work, but a project with
+//! uncommitted edits, stashes or commits that exist nowhere else is one
+//! somebody is in the middle of, and worth a second look before cleaning.
+//! Everything here is a cheap approximation of what `git status` reports:
+//! tracked files are compared to the index by size and modification time
+//! without hashing them, and `.gitignore` files are read with the same
+//! pattern rules as `scan_patterns`.
+
+use crate::patterns::PathFilter;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A repository found for a project
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Repo {
+    /// Top of the checkout, holding `.git`
+    pub worktree: PathBuf,
+    /// `HEAD`, the index and the HEAD reflog live here
+    pub git_dir: PathBuf,
+    /// Refs, `packed-refs` and `config` live here; differs from `git_dir`
+    /// for linked worktrees
+    pub common_dir: PathBuf,
+}
+
+/// What a repository looked like when it was scanned
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GitState {
+    /// Checked out branch, `None` when HEAD is detached
+    pub branch: Option<String>,
+    pub last_commit: Option<SystemTime>,
+    /// When something was last staged, committed or checked out
+    pub index_modified: Option<SystemTime>,
+    /// Tracked files that were edited or deleted since they were staged
+    pub modified_files: usize,
+    /// Some file is neither tracked nor ignored
+    pub has_untracked: bool,
+    pub stashes: usize,
+    /// HEAD has commits its upstream has not seen, or is detached at a
+    /// commit no ref holds on to. A branch without an upstream has nowhere
+    /// to push to, so it never counts.
+    pub unpushed: bool,
+}
+
+impl GitState {
+    /// Uncommitted changes in the working tree
+    pub fn is_dirty(&self) -> bool {
+        self.modified_files > 0 || self.has_untracked
+    }
+
+    /// Work that exists only in this checkout
+    pub fn has_local_work(&self) -> bool {
+        self.is_dirty() || self.unpushed || self.stashes > 0
+    }
+
+    /// Short description for cards and tables, e.g. `main, 3 modified, unpushed`
+    pub fn summary(&self) -> String {
+        let mut parts = vec![self
+            .branch
+            .clone()
+            .unwrap_or_else(|| "detached".to_string())];
+        if self.modified_files > 0 {
+            parts.push(format!("{} modified", self.modified_files));
+        }
+        if self.has_untracked {
+            parts.push("untracked files".to_string());
+        }
+        if self.stashes > 0 {
+            parts.push(format!("{} stashed", self.stashes));
+        }
+        if self.unpushed {
+            parts.push("unpushed".to_string());
+        }
+        parts.join(", ")
+    }
+}
+
+/// The repository containing `project`.
+///
+/// Looks in `project` and its ancestors, but not in the home directory or
+/// above, where a dotfiles repository would claim every project.
+pub fn find_repo(project: &Path) -> Option<Repo> {
+    let home = dirs::home_dir();
+    for dir in project.ancestors() {
+        if home.as_deref().is_some_and(|home| home.starts_with(dir)) {
+            return None;
+        }
+        let dot_git = dir.join(".git");
+        let git_dir = if dot_git.is_dir() {
+            dot_git
+        } else if let Ok(contents) = fs::read_to_string(&dot_git) {
+            // Worktrees and submodules have a `.git` file pointing elsewhere
+            dir.join(contents.trim().strip_prefix("gitdir:")?.trim())
+        } else {
+            continue;
+        };
+        let common_dir = match fs::read_to_string(git_dir.join("commondir")) {
+            Ok(common) => git_dir.join(common.trim()),
+            Err(_) => git_dir.clone(),
+        };
+        return Some(Repo {
+            worktree: dir.to_path_buf(),
+            git_dir,
+            common_dir,
+        });
+    }
+    None
+}
+
+/// When the newest commit was made in this clone, from the HEAD reflog
+pub fn last_commit_time(git_dir: &Path) -> Option<SystemTime> {
+    let log = fs::read_to_string(git_dir.join("logs").join("HEAD")).ok()?;
+    log.lines().rev().find_map(|line| {
+        // `<old> <new> <name> <<email>> <seconds> <zone>\t<message>`
+        let (entry, message) = line.split_once('\t')?;
+        if !message.starts_with("commit") {
+            return None;
+        }
+        let mut fields = entry.rsplit(' ');
+        let _zone = fields.next()?;
+        let seconds: u64 = fields.next()?.parse().ok()?;
+        Some(UNIX_EPOCH + Duration::from_secs(seconds))
+    })
+}
+
+/// Read the state of `repo`. `is_artifact` keeps the search for untracked
+/// files out of build output that was never ignored.
+pub fn read_state(repo: &Repo, is_artifact: &dyn Fn(&Path) -> bool) -> GitState {
+    let head = fs::read_to_string(repo.git_dir.join("HEAD")).unwrap_or_default();
+    let head = head.trim();
+    let (branch, head_commit) = match head.strip_prefix("ref: ") {
+        Some(reference) => (
+            reference.strip_prefix("refs/heads/").map(str::to_string),
+            resolve_ref(&repo.common_dir, reference),
+        ),
+        None => (None, Some(head.to_string()).filter(|sha| !sha.is_empty())),
+    };
+
+    let index_path = repo.git_dir.join("index");
+    let tracked = read_index(&index_path, hash_len(&repo.common_dir)).unwrap_or_default();
+    let modified_files = tracked
+        .iter()
+        .filter(|entry| entry.is_modified(&repo.worktree))
+        .count();
+    let tracked_paths: HashSet<&Path> = tracked.iter().map(|e| e.path.as_path()).collect();
+    // Later ignore files take precedence, as in git
+    let mut ignores: Vec<PathFilter> = [
+        excludes_file(&repo.common_dir),
+        Some(repo.common_dir.join("info").join("exclude")),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|path| read_ignore_file(&path, &repo.worktree))
+    .collect();
+    let has_untracked = has_untracked(
+        &repo.worktree,
+        &repo.worktree,
+        &tracked_paths,
+        &mut ignores,
+        is_artifact,
+    );
+
+    let stashes = fs::read_to_string(repo.common_dir.join("logs/refs/stash"))
+        .map(|log| log.lines().count())
+        .unwrap_or_else(|_| usize::from(resolve_ref(&repo.common_dir, "refs/stash").is_some()));
+
+    GitState {
+        unpushed: head_commit
+            .as_deref()
+            .is_some_and(|commit| is_unpushed(&repo.common_dir, branch.as_deref(), commit)),
+        branch,
+        last_commit: last_commit_time(&repo.git_dir),
+        index_modified: fs::metadata(&index_path).and_then(|m| m.modified()).ok(),
+        modified_files,
+        has_untracked,
+        stashes,
+    }
+}
+
+/// Commit a ref points at, following symbolic refs, from a loose ref file
+/// or `packed-refs`
+fn resolve_ref(common_dir: &Path, reference: &str) -> Option<String> {
+    let mut reference = reference.to_string();
+    // Symbolic refs can chain, but never deeply
+    for _ in 0..5 {
+        let value = match fs::read_to_string(common_dir.join(&reference)) {
+            Ok(value) => value.trim().to_string(),
+            Err(_) => {
+                return packed_refs(common_dir)
+                    .into_iter()
+                    .find(|(name, _)| *name == reference)
+                    .map(|(_, commit)| commit)
+            }
+        };
+        match value.strip_prefix("ref: ") {
+            Some(target) => reference = target.to_string(),
+            None => return Some(value),
+        }
+    }
+    None
+}
+
+/// `(ref name, commit)` pairs from `packed-refs`
+fn packed_refs(common_dir: &Path) -> Vec<(String, String)> {
+    let contents = fs::read_to_string(common_dir.join("packed-refs")).unwrap_or_default();
+    contents
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.starts_with('^'))
+        .filter_map(|line| {
+            let (commit, name) = line.split_once(' ')?;
+            Some((name.to_string(), commit.to_string()))
+        })
+        .collect()
+}
+
+/// Every commit the remote-tracking branches point at or have pointed at,
+/// per their reflogs
+fn remote_commits(common_dir: &Path) -> HashSet<String> {
+    let mut commits: HashSet<String> = packed_refs(common_dir)
+        .into_iter()
+        .filter(|(name, _)| name.starts_with("refs/remotes/"))
+        .map(|(_, commit)| commit)
+        .collect();
+    for base in ["refs/remotes", "logs/refs/remotes"] {
+        collect_ref_files(&common_dir.join(base), &mut |contents| {
+            for line in contents.lines() {
+                // Ref files hold one commit, reflog lines start with old and new
+                commits.extend(line.split(' ').take(2).map(str::to_string));
+            }
+        });
+    }
+    commits
+}
+
+fn collect_ref_files(dir: &Path, visit: &mut dyn FnMut(&str)) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_ref_files(&path, visit);
+        } else if let Ok(contents) = fs::read_to_string(&path) {
+            visit(&contents);
+        }
+    }
+}
+
+/// Every commit a branch, tag or remote-tracking ref points at
+fn ref_commits(common_dir: &Path) -> HashSet<String> {
+    let mut commits: HashSet<String> = packed_refs(common_dir)
+        .into_iter()
+        .map(|(_, commit)| commit)
+        .collect();
+    collect_ref_files(&common_dir.join("refs"), &mut |contents| {
+        commits.insert(contents.trim().to_string());
+    });
+    commits
+}
+
+/// Whether `head` holds work that would be lost with the checkout.
+///
+/// On a branch with an upstream that is `head` missing from everything
+/// fetched from or pushed to a remote. Without reading objects a branch that
+/// is merely behind its upstream cannot be told from one that is ahead, but
+/// a commit that was pushed always shows up in the remote-tracking reflog.
+/// A detached `head` counts when no ref points at it, since checking out
+/// anything else leaves it unreachable.
+fn is_unpushed(common_dir: &Path, branch: Option<&str>, head: &str) -> bool {
+    let Some(branch) = branch else {
+        return !ref_commits(common_dir).contains(head)
+            && !remote_commits(common_dir).contains(head);
+    };
+    let Some((remote, merge)) = upstream(common_dir, branch) else {
+        return false;
+    };
+    let upstream_ref = match merge.strip_prefix("refs/heads/") {
+        Some(name) if remote != "." => format!("refs/remotes/{}/{}", remote, name),
+        _ => merge,
+    };
+    if resolve_ref(common_dir, &upstream_ref).as_deref() == Some(head) {
+        return false;
+    }
+    !remote_commits(common_dir).contains(head)
+}
+
+/// `(remote, merge)` configured for `branch` in the repository config
+fn upstream(common_dir: &Path, branch: &str) -> Option<(String, String)> {
+    let config = fs::read_to_string(common_dir.join("config")).ok()?;
+    let section = format!("[branch \"{}\"]", branch);
+    let (mut remote, mut merge) = (None, None);
+    let mut in_section = false;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_section = line == section;
+        } else if let (true, Some((key, value))) = (in_section, line.split_once('=')) {
+            match key.trim() {
+                "remote" => remote = Some(value.trim().to_string()),
+                "merge" => merge = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+    }
+    Some((remote?, merge?))
+}
+
+/// Length of object ids, 32 bytes in SHA-256 repositories
+fn hash_len(common_dir: &Path) -> usize {
+    let config = fs::read_to_string(common_dir.join("config")).unwrap_or_default();
+    let sha256 = config.lines().any(|line| {
+        let line = line.trim().to_ascii_lowercase();
+        line.starts_with("objectformat") && line.ends_with("sha256")
+    });
+    if sha256 {
+        32
+    } else {
+        20
+    }
+}
+
+/// A file in the index with the stat data it was staged with
+struct IndexEntry {
+    path: PathBuf,
+    mtime_secs: u32,
+    mtime_nanos: u32,
+    size: u32,
+}
+
+impl IndexEntry {
+    fn is_modified(&self, worktree: &Path) -> bool {
+        let Ok(metadata) = fs::symlink_metadata(worktree.join(&self.path)) else {
+            return true; // Deleted
+        };
+        let Ok(modified) = metadata.modified() else {
+            return false;
+        };
+        let since_epoch = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+        // The index truncates both to 32 bits
+        metadata.len() as u32 != self.size
+            || since_epoch.as_secs() as u32 != self.mtime_secs
+            // Git built without nanosecond support stores 0
+            || (self.mtime_nanos != 0 && since_epoch.subsec_nanos() != self.mtime_nanos)
+    }
+}
+
+/// Parse the entries of a version 2, 3 or 4 index file, leaving out
+/// submodules and entries git is told not to check
+fn read_index(path: &Path, hash_len: usize) -> Option<Vec<IndexEntry>> {
+    let data = fs::read(path).ok()?;
+    let u32_at = |offset: usize| -> Option<u32> {
+        let bytes = data.get(offset..offset + 4)?;
+        Some(u32::from_be_bytes(bytes.try_into().ok()?))
+    };
+    if data.get(0..4)? != b"DIRC" {
+        return None;
+    }
+    let version = u32_at(4)?;
+    let count = u32_at(8)? as usize;
+    if !(2..=4).contains(&version) {
+        return None;
+    }
+
+    let mut entries = Vec::with_capacity(count);
+    let mut offset = 12;
+    let mut previous_name: Vec<u8> = Vec::new();
+    for _ in 0..count {
+        let start = offset;
+        let mtime_secs = u32_at(start + 8)?;
+        let mtime_nanos = u32_at(start + 12)?;
+        let mode = u32_at(start + 24)?;
+        let size = u32_at(start + 36)?;
+        let flags_at = start + 40 + hash_len;
+        let flags = u16::from_be_bytes(data.get(flags_at..flags_at + 2)?.try_into().ok()?);
+        offset = flags_at + 2;
+        let mut skip_worktree = false;
+        if flags & 0x4000 != 0 {
+            let extended = u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?);
+            skip_worktree = extended & 0x4000 != 0;
+            offset += 2;
+        }
+
+        let name = if version == 4 {
+            // Prefix-compressed: drop N bytes of the previous name, then append
+            let mut strip = 0usize;
+            loop {
+                let byte = *data.get(offset)?;
+                offset += 1;
+                strip = (strip << 7) | (byte & 0x7f) as usize;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+                strip += 1;
+            }
+            let end = offset + data.get(offset..)?.iter().position(|&b| b == 0)?;
+            let mut name = previous_name[..previous_name.len().checked_sub(strip)?].to_vec();
+            name.extend_from_slice(&data[offset..end]);
+            offset = end + 1;
+            name
+        } else {
+            let end = offset + data.get(offset..)?.iter().position(|&b| b == 0)?;
+            let name = data[offset..end].to_vec();
+            // Entries are padded with 1 to 8 NULs to a multiple of 8 bytes
+            offset = start + (end - start + 8) / 8 * 8;
+            name
+        };
+
+        let assume_valid = flags & 0x8000 != 0;
+        let submodule = mode & 0o170000 == 0o160000;
+        if !assume_valid && !skip_worktree && !submodule {
+            entries.push(IndexEntry {
+                path: path_from_bytes(&name),
+                mtime_secs,
+                mtime_nanos,
+                size,
+            });
+        }
+        previous_name = name;
+    }
+    Some(entries)
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Patterns from a `.gitignore`, `info/exclude` or global excludes file,
+/// relative to `base`
+fn read_ignore_file(path: &Path, base: &Path) -> Option<PathFilter> {
+    let contents = fs::read_to_string(path).ok()?;
+    let lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    PathFilter::new(&lines, vec![base.to_path_buf()]).ok()
+}
+
+/// The user's global ignore file: `core.excludesFile` from the repository
+/// or user config, or else git's default of `$XDG_CONFIG_HOME/git/ignore`
+fn excludes_file(common_dir: &Path) -> Option<PathBuf> {
+    let home = dirs::home_dir()?;
+    let xdg_config = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".config"));
+
+    // Most specific first: the repository, then ~/.gitconfig over the XDG file
+    let configured = [
+        common_dir.join("config"),
+        home.join(".gitconfig"),
+        xdg_config.join("git").join("config"),
+    ]
+    .iter()
+    .find_map(|path| core_value(&fs::read_to_string(path).ok()?, "excludesfile"));
+
+    Some(match configured {
+        Some(value) => match value.strip_prefix("~/") {
+            Some(rest) => home.join(rest),
+            None => PathBuf::from(value),
+        },
+        None => xdg_config.join("git").join("ignore"),
+    })
+}
+
+/// The last value of `key` in the `[core]` section of a git config. Section
+/// and key names are case-insensitive.
+fn core_value(config: &str, key: &str) -> Option<String> {
+    let mut in_core = false;
+    let mut value = None;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_core = line.eq_ignore_ascii_case("[core]");
+        } else if let (true, Some((name, found))) = (in_core, line.split_once('=')) {
+            if name.trim().eq_ignore_ascii_case(key) {
+                value = Some(found.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+    value
+}
+
+/// Whether any file below `dir` is neither in `tracked` nor ignored, stopping
+/// at the first. `ignores` holds the ignore files of `dir` and its parents,
+/// shallowest first.
+fn has_untracked(
+    worktree: &Path,
+    dir: &Path,
+    tracked: &HashSet<&Path>,
+    ignores: &mut Vec<PathFilter>,
+    is_artifact: &dyn Fn(&Path) -> bool,
+) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    let own_ignore = read_ignore_file(&dir.join(".gitignore"), dir);
+    let pushed = own_ignore.is_some();
+    ignores.extend(own_ignore);
+
+    let mut found = false;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let ignored = ignores.iter().rev().find_map(|filter| filter.decide(&path));
+        if ignored == Some(true) {
+            continue;
+        }
+        // DirEntry::file_type does not follow symlinks, and git tracks links as files
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            // Nested repositories track their own files
+            if !path.join(".git").exists() && !is_artifact(&path) {
+                found = has_untracked(worktree, &path, tracked, ignores, is_artifact);
+            }
+        } else if let Ok(relative) = path.strip_prefix(worktree) {
+            found = !tracked.contains(relative);
+        }
+        if found {
+            break;
+        }
+    }
+
+    if pushed {
+        ignores.pop();
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory under the system temp dir, unique to `name`
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dsc-git-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// The stat fields, object id and flags of one index entry
+    fn entry_header(mtime: u32, mode: u32, size: u32, flags: u16) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for field in [0, 0, mtime, 7, 0, 0, mode, 0, 0, size] {
+            bytes.extend_from_slice(&field.to_be_bytes());
+        }
+        bytes.extend_from_slice(&[0xab; 20]);
+        bytes.extend_from_slice(&flags.to_be_bytes());
+        bytes
+    }
+
+    fn index_file(version: u32, entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = b"DIRC".to_vec();
+        data.extend_from_slice(&version.to_be_bytes());
+        data.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        for entry in entries {
+            data.extend_from_slice(entry);
+        }
+        data
+    }
+
+    fn v2_entry(name: &str, mode: u32, size: u32) -> Vec<u8> {
+        let mut bytes = entry_header(1_700_000_000, mode, size, name.len() as u16);
+        bytes.extend_from_slice(name.as_bytes());
+        // Padded with 1 to 8 NULs to a multiple of 8
+        let padding = 8 - bytes.len() % 8;
+        bytes.extend(std::iter::repeat_n(0, padding));
+        bytes
+    }
+
+    fn v4_entry(strip: u8, suffix: &str) -> Vec<u8> {
+        let mut bytes = entry_header(1_700_000_000, 0o100644, 3, suffix.len() as u16);
+        bytes.push(strip);
+        bytes.extend_from_slice(suffix.as_bytes());
+        bytes.push(0);
+        bytes
+    }
+
+    fn parse(name: &str, data: &[u8]) -> Vec<IndexEntry> {
+        let path = temp_dir(name).join("index");
+        fs::write(&path, data).unwrap();
+        read_index(&path, 20).unwrap()
+    }
+
+    fn paths(entries: &[IndexEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.path.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn reads_version_2_index() {
+        let data = index_file(
+            2,
+            &[
+                v2_entry("Cargo.toml", 0o100644, 120),
+                v2_entry("src/main.rs", 0o100644, 4096),
+                // A name that fills its entry exactly still gets 8 NULs
+                v2_entry("src/lib.rs", 0o100644, 1),
+                v2_entry("vendor/sub", 0o160000, 0),
+            ],
+        );
+        let entries = parse("v2", &data);
+        assert_eq!(paths(&entries), ["Cargo.toml", "src/main.rs", "src/lib.rs"]);
+        assert_eq!(entries[1].size, 4096);
+        assert_eq!(entries[1].mtime_secs, 1_700_000_000);
+        assert_eq!(entries[1].mtime_nanos, 7);
+    }
+
+    #[test]
+    fn reads_version_4_index() {
+        let data = index_file(
+            4,
+            &[
+                v4_entry(0, "src/app.rs"),
+                // Drop `app.rs` and append `cli.rs`
+                v4_entry(6, "cli.rs"),
+                v4_entry(10, "tests/cli.rs"),
+                v4_entry(6, "main.rs"),
+            ],
+        );
+        assert_eq!(
+            paths(&parse("v4", &data)),
+            ["src/app.rs", "src/cli.rs", "tests/cli.rs", "tests/main.rs"]
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_index() {
+        let mut data = index_file(2, &[v2_entry("Cargo.toml", 0o100644, 1)]);
+        data.truncate(data.len() - 12);
+        let path = temp_dir("truncated").join("index");
+        fs::write(&path, data).unwrap();
+        assert!(read_index(&path, 20).is_none());
+    }
+
+    const HEAD: &str = "1111111111111111111111111111111111111111";
+    const OTHER: &str = "2222222222222222222222222222222222222222";
+
+    fn repo(name: &str, config: &str, refs: &[(&str, &str)]) -> PathBuf {
+        let dir = temp_dir(name);
+        fs::write(dir.join("config"), config).unwrap();
+        for (name, commit) in refs {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, format!("{}\n", commit)).unwrap();
+        }
+        dir
+    }
+
+    const TRACKING: &str = "[branch \"main\"]\n\tremote = origin\n\tmerge = refs/heads/main\n";
+
+    #[test]
+    fn branch_without_upstream_is_not_unpushed() {
+        let dir = repo("no-upstream", "[core]\n", &[("refs/heads/main", HEAD)]);
+        assert!(!is_unpushed(&dir, Some("main"), HEAD));
+    }
+
+    #[test]
+    fn branch_ahead_of_upstream_is_unpushed() {
+        let dir = repo(
+            "ahead",
+            TRACKING,
+            &[
+                ("refs/heads/main", HEAD),
+                ("refs/remotes/origin/main", OTHER),
+            ],
+        );
+        assert!(is_unpushed(&dir, Some("main"), HEAD));
+    }
+
+    #[test]
+    fn branch_at_upstream_is_not_unpushed() {
+        let dir = repo(
+            "even",
+            TRACKING,
+            &[
+                ("refs/heads/main", HEAD),
+                ("refs/remotes/origin/main", HEAD),
+            ],
+        );
+        assert!(!is_unpushed(&dir, Some("main"), HEAD));
+    }
+
+    #[test]
+    fn detached_head_counts_only_without_a_ref() {
+        let dir = repo("detached", "[core]\n", &[("refs/tags/v1", OTHER)]);
+        assert!(is_unpushed(&dir, None, HEAD));
+        assert!(!is_unpushed(&dir, None, OTHER));
+    }
+
+    #[test]
+    fn reads_core_excludes_file() {
+        let config = "[user]\n\tname = a\n[Core]\n\texcludesFile = \"~/ignore\"\n\tbare = false\n";
+        assert_eq!(
+            core_value(config, "excludesfile").as_deref(),
+            Some("~/ignore")
+        );
+        assert_eq!(
+            core_value("[user]\n\texcludesfile = x\n", "excludesfile"),
+            None
+        );
+    }
+
+    #[test]
+    fn untracked_files_honor_excludes() {
+        let worktree = temp_dir("untracked");
+        fs::write(worktree.join("tracked.rs"), "").unwrap();
+        fs::write(worktree.join("notes.swp"), "").unwrap();
+        let tracked: HashSet<&Path> = [Path::new("tracked.rs")].into_iter().collect();
+        let no_artifacts = |_: &Path| false;
+
+        let mut ignores = Vec::new();
+        assert!(has_untracked(
+            &worktree,
+            &worktree,
+            &tracked,
+            &mut ignores,
+            &no_artifacts
+        ));
+
+        let excludes = temp_dir("excludes").join("ignore");
+        fs::write(&excludes, "*.swp\n").unwrap();
+        let mut ignores: Vec<PathFilter> =
+            read_ignore_file(&excludes, &worktree).into_iter().collect();
+        assert!(!has_untracked(
+            &worktree,
+            &worktree,
+            &tracked,
+            &mut ignores,
+            &no_artifacts
+        ));
+        assert_eq!(ignores.len(), 1);
+    }
+}
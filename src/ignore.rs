@@ -0,0 +1,78 @@
+//! Per-directory `.devstorageignore` files.
+//!
+//! A `.devstorageignore` holds patterns in the same syntax as `scan_patterns`,
+//! relative to the directory it sits in. Deeper files take precedence over
+//! shallower ones, every file over the config patterns, and within a file the
+//! last matching line wins, so `!` can re-include what a parent excluded. A
+//! file without any patterns opts its own directory out entirely.
+//!
+//! Files in the ancestors of a scan root count too, which lets the cleaner
+//! check any path on its own.
+
+use crate::errors::ErrorLog;
+use crate::patterns::PathFilter;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const IGNORE_FILE: &str = ".devstorageignore";
+
+/// The ignore files in effect inside one directory, shallowest first
+#[derive(Clone, Debug, Default)]
+pub struct IgnoreStack {
+    files: Vec<Arc<PathFilter>>,
+}
+
+impl IgnoreStack {
+    /// Rules in effect inside `dir`, from its own ignore file and those of
+    /// its ancestors. `None` if `dir` or one of its ancestors is ignored.
+    /// Files that do not parse are skipped and noted in `errors`.
+    pub fn for_dir(dir: &Path, errors: &ErrorLog) -> Option<Self> {
+        let mut stack = IgnoreStack::default();
+        let mut current = PathBuf::new();
+        for component in dir.components() {
+            current.push(component);
+            if stack.decide(&current) == Some(true) {
+                return None;
+            }
+            stack = stack.descend(&current, errors)?;
+        }
+        Some(stack)
+    }
+
+    /// Rules in effect inside `dir`, a subdirectory of the one this stack
+    /// is for. `None` if `dir` opts out with an empty ignore file.
+    pub fn descend(&self, dir: &Path, errors: &ErrorLog) -> Option<Self> {
+        let path = dir.join(IGNORE_FILE);
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Some(self.clone());
+        };
+        let lines: Vec<String> = contents.lines().map(str::to_string).collect();
+        let filter = match PathFilter::new(&lines, vec![dir.to_path_buf()]) {
+            Ok(filter) => filter,
+            Err(e) => {
+                errors.record_invalid_ignore_file(&path, e);
+                return Some(self.clone());
+            }
+        };
+        if filter.is_empty() {
+            return None;
+        }
+
+        let mut files = self.files.clone();
+        files.push(Arc::new(filter));
+        Some(Self { files })
+    }
+
+    /// Verdict of the deepest ignore file with a pattern matching `path`
+    pub fn decide(&self, path: &Path) -> Option<bool> {
+        self.files.iter().rev().find_map(|file| file.decide(path))
+    }
+}
+
+/// Whether an ignore file in `path` or any of its ancestors excludes it.
+/// Files that do not parse are skipped silently; the scan that found `path`
+/// already reported them.
+pub fn is_ignored(path: &Path) -> bool {
+    IgnoreStack::for_dir(path, &ErrorLog::default()).is_none()
+}
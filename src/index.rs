@@ -0,0 +1,159 @@
+//! Persistent index of previously found artifacts.
+//!
+//! Sizing a tree is the slow part of a scan, so every measured artifact is
+//! recorded in `<config dir>/index.json` together with a fingerprint of its
+//! directory metadata. A rescan reuses the stored size while the fingerprint
+//! is unchanged, and the app shows the indexed artifacts straight away while
+//! the rescan verifies them.
+
+use crate::activity::ActivitySignal;
+use crate::config::Config;
+use crate::detectors::ArtifactKind;
+use crate::scanner::ProjectInfo;
+use crate::usage::DiskUsage;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub project_path: PathBuf,
+    pub artifact_path: PathBuf,
+    pub kind: ArtifactKind,
+    pub marker: String,
+    pub last_modified: SystemTime,
+    /// Missing from indexes written before activity was tracked
+    #[serde(default)]
+    pub last_active: Option<SystemTime>,
+    #[serde(default)]
+    pub activity: Option<ActivitySignal>,
+    /// See [`fingerprint`]
+    pub fingerprint: SystemTime,
+    pub size_bytes: u64,
+    pub disk_bytes: u64,
+}
+
+impl IndexEntry {
+    fn to_project(&self) -> ProjectInfo {
+        ProjectInfo {
+            project_path: self.project_path.clone(),
+            artifact_path: self.artifact_path.clone(),
+            kind: self.kind,
+            marker: self.marker.clone(),
+            last_modified: self.last_modified,
+            last_active: self.last_active.unwrap_or(self.last_modified),
+            activity: self.activity.unwrap_or(ActivitySignal::Artifact),
+            // Read again by the rescan that follows
+            git: None,
+            rule: None,
+            size_bytes: self.size_bytes,
+            disk_bytes: self.disk_bytes,
+            selected: false,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ScanIndex {
+    entries: HashMap<PathBuf, IndexEntry>,
+}
+
+impl ScanIndex {
+    fn index_path() -> PathBuf {
+        Config::config_dir().join("index.json")
+    }
+
+    /// Load the index, starting empty if it is missing or unreadable
+    pub fn load() -> Self {
+        let entries: Vec<IndexEntry> = fs::read_to_string(Self::index_path())
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        Self {
+            entries: entries
+                .into_iter()
+                .map(|entry| (entry.artifact_path.clone(), entry))
+                .collect(),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let mut entries: Vec<&IndexEntry> = self.entries.values().collect();
+        entries.sort_by(|a, b| a.artifact_path.cmp(&b.artifact_path));
+        let contents = serde_json::to_string_pretty(&entries)?;
+        fs::write(Self::index_path(), contents)
+    }
+
+    /// Indexed artifacts under `root` that still exist, largest first
+    pub fn cached_projects(&self, root: &Path) -> Vec<ProjectInfo> {
+        let mut projects: Vec<ProjectInfo> = self
+            .entries
+            .values()
+            .filter(|e| e.artifact_path.starts_with(root) && e.artifact_path.is_dir())
+            .map(IndexEntry::to_project)
+            .collect();
+        projects.sort_by_key(|p| std::cmp::Reverse(p.disk_bytes));
+        projects
+    }
+
+    /// Stored usage of `path`, if it was indexed with the same fingerprint
+    pub fn lookup(&self, path: &Path, fingerprint: SystemTime) -> Option<DiskUsage> {
+        self.entries
+            .get(path)
+            .filter(|e| e.fingerprint == fingerprint)
+            .map(|e| DiskUsage {
+                apparent_bytes: e.size_bytes,
+                disk_bytes: e.disk_bytes,
+            })
+    }
+
+    pub fn insert(&mut self, project: &ProjectInfo, fingerprint: SystemTime) {
+        self.entries.insert(
+            project.artifact_path.clone(),
+            IndexEntry {
+                project_path: project.project_path.clone(),
+                artifact_path: project.artifact_path.clone(),
+                kind: project.kind,
+                marker: project.marker.clone(),
+                last_modified: project.last_modified,
+                last_active: Some(project.last_active),
+                activity: Some(project.activity),
+                fingerprint,
+                size_bytes: project.size_bytes,
+                disk_bytes: project.disk_bytes,
+            },
+        );
+    }
+
+    /// Forget artifacts under `root` that a complete scan of it did not
+    /// detect. `detected` includes artifacts below the age threshold, which
+    /// still exist and keep their sizes.
+    pub fn prune(&mut self, root: &Path, detected: &HashSet<PathBuf>) {
+        self.entries
+            .retain(|path, _| !path.starts_with(root) || detected.contains(path));
+    }
+}
+
+/// Newest modification time of `path` and its immediate subdirectories.
+///
+/// Installing, removing or rebuilding something inside an artifact almost
+/// always adds or replaces an entry at one of these two levels, which is
+/// far cheaper to check than re-walking the whole tree.
+pub fn fingerprint(path: &Path) -> Option<SystemTime> {
+    let mut newest = fs::symlink_metadata(path).ok()?.modified().ok()?;
+    for entry in fs::read_dir(path).ok()?.filter_map(|e| e.ok()) {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            if let Ok(modified) = metadata.modified() {
+                newest = newest.max(modified);
+            }
+        }
+    }
+    Some(newest)
+}
@@ -0,0 +1,368 @@
+//! Order and grouping of the project list.
+//!
+//! Grouped lists are sorted group by group, so every group is one contiguous
+//! run of projects and positions in the list stay valid for selection.
+
+use crate::scanner::ProjectInfo;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// Space freed on disk
+    #[default]
+    Size,
+    /// Days since the project was last active
+    Age,
+    /// Project folder name
+    Name,
+    /// Full artifact path
+    Path,
+    /// Artifact kind, e.g. `node_modules`
+    Kind,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 5] = [
+        SortKey::Size,
+        SortKey::Age,
+        SortKey::Name,
+        SortKey::Path,
+        SortKey::Kind,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortKey::Size => "SIZE",
+            SortKey::Age => "AGE",
+            SortKey::Name => "NAME",
+            SortKey::Path => "PATH",
+            SortKey::Kind => "KIND",
+        }
+    }
+
+    /// The key after this one, for cycling through them in the UI
+    pub fn next(&self) -> Self {
+        let position = Self::ALL.iter().position(|k| k == self).unwrap_or(0);
+        Self::ALL[(position + 1) % Self::ALL.len()]
+    }
+
+    /// Largest and oldest first, names and paths from A to Z
+    pub fn ascending_by_default(&self) -> bool {
+        !matches!(self, SortKey::Size | SortKey::Age)
+    }
+
+    fn compare(&self, a: &ProjectInfo, b: &ProjectInfo) -> Ordering {
+        match self {
+            SortKey::Size => a.disk_bytes.cmp(&b.disk_bytes),
+            SortKey::Age => a.last_active.cmp(&b.last_active).reverse(),
+            SortKey::Name => project_name(a).cmp(&project_name(b)),
+            SortKey::Path => a.artifact_path.cmp(&b.artifact_path),
+            SortKey::Kind => a.kind.label().cmp(b.kind.label()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    #[default]
+    None,
+    /// The scan root each artifact was found under
+    Root,
+    /// The folder holding each project
+    Parent,
+}
+
+impl GroupBy {
+    pub const ALL: [GroupBy; 3] = [GroupBy::None, GroupBy::Root, GroupBy::Parent];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GroupBy::None => "NONE",
+            GroupBy::Root => "ROOT",
+            GroupBy::Parent => "FOLDER",
+        }
+    }
+
+    /// The grouping after this one, for cycling through them in the UI
+    pub fn next(&self) -> Self {
+        let position = Self::ALL.iter().position(|g| g == self).unwrap_or(0);
+        Self::ALL[(position + 1) % Self::ALL.len()]
+    }
+
+    /// The group `project` belongs to, empty for projects outside every
+    /// root or when not grouping
+    pub fn group_of(&self, project: &ProjectInfo, roots: &[PathBuf]) -> PathBuf {
+        match self {
+            GroupBy::None => PathBuf::new(),
+            GroupBy::Root => roots
+                .iter()
+                .find(|root| project.artifact_path.starts_with(root))
+                .cloned()
+                .unwrap_or_default(),
+            GroupBy::Parent => project
+                .project_path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// How the project list is laid out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ListOrder {
+    pub key: SortKey,
+    pub ascending: bool,
+    pub group_by: GroupBy,
+}
+
+/// A run of projects sharing a group
+#[derive(Clone, Debug)]
+pub struct Group {
+    /// Empty when not grouping, or for projects outside every root
+    pub path: PathBuf,
+    /// Positions of the group's projects in the list
+    pub range: Range<usize>,
+    pub disk_bytes: u64,
+}
+
+impl Group {
+    pub fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    pub fn size_gb(&self) -> f64 {
+        self.disk_bytes as f64 / (1024.0 * 1024.0 * 1024.0)
+    }
+}
+
+/// Sort `projects` by `order`, keeping each group together. Groups follow
+/// their total size when sorting by size and their path otherwise.
+pub fn arrange(projects: &mut [ProjectInfo], order: ListOrder, roots: &[PathBuf]) {
+    let directed = |ordering: Ordering| {
+        if order.ascending {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    };
+    // Ties fall back to the path so the order is the same on every rescan
+    projects.sort_by(|a, b| {
+        directed(order.key.compare(a, b)).then_with(|| a.artifact_path.cmp(&b.artifact_path))
+    });
+    if order.group_by == GroupBy::None {
+        return;
+    }
+
+    let mut totals: HashMap<PathBuf, u64> = HashMap::new();
+    for project in projects.iter() {
+        *totals
+            .entry(order.group_by.group_of(project, roots))
+            .or_default() += project.disk_bytes;
+    }
+    let mut ranked: Vec<(&PathBuf, &u64)> = totals.iter().collect();
+    ranked.sort_by(|(a, a_total), (b, b_total)| match order.key {
+        SortKey::Size => directed(a_total.cmp(b_total)).then_with(|| a.cmp(b)),
+        _ => directed(a.cmp(b)),
+    });
+    let rank: HashMap<&PathBuf, usize> = ranked
+        .into_iter()
+        .enumerate()
+        .map(|(rank, (path, _))| (path, rank))
+        .collect();
+    // Stable, so projects keep their order within a group
+    projects.sort_by_cached_key(|project| rank[&order.group_by.group_of(project, roots)]);
+}
+
+/// The groups of a list arranged by [`arrange`], in order
+pub fn groups(projects: &[ProjectInfo], group_by: GroupBy, roots: &[PathBuf]) -> Vec<Group> {
+    let mut groups: Vec<Group> = Vec::new();
+    for (index, project) in projects.iter().enumerate() {
+        let path = group_by.group_of(project, roots);
+        match groups.last_mut() {
+            Some(group) if group.path == path => {
+                group.range.end = index + 1;
+                group.disk_bytes += project.disk_bytes;
+            }
+            _ => groups.push(Group {
+                path,
+                range: index..index + 1,
+                disk_bytes: project.disk_bytes,
+            }),
+        }
+    }
+    groups
+}
+
+fn project_name(project: &ProjectInfo) -> String {
+    project
+        .project_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activity::ActivitySignal;
+    use crate::detectors::ArtifactKind;
+    use std::time::{Duration, SystemTime};
+
+    fn project(artifact: &str, kind: ArtifactKind, disk_bytes: u64, days_old: u64) -> ProjectInfo {
+        let artifact_path = PathBuf::from(artifact);
+        let last_active = SystemTime::now() - Duration::from_secs(days_old * 86400);
+        ProjectInfo {
+            project_path: artifact_path.parent().unwrap().to_path_buf(),
+            artifact_path,
+            kind,
+            marker: String::new(),
+            last_modified: last_active,
+            last_active,
+            activity: ActivitySignal::Artifact,
+            git: None,
+            rule: None,
+            size_bytes: disk_bytes,
+            disk_bytes,
+            selected: false,
+        }
+    }
+
+    fn sample() -> Vec<ProjectInfo> {
+        vec![
+            project("/work/app/node_modules", ArtifactKind::NodeModules, 30, 10),
+            project("/work/zed/target", ArtifactKind::CargoTarget, 10, 50),
+            project("/other/beta/dist", ArtifactKind::Dist, 20, 5),
+            project("/elsewhere/loose/dist", ArtifactKind::Dist, 25, 20),
+        ]
+    }
+
+    fn roots() -> Vec<PathBuf> {
+        vec![PathBuf::from("/work"), PathBuf::from("/other")]
+    }
+
+    fn arranged(key: SortKey, ascending: bool, group_by: GroupBy) -> Vec<ProjectInfo> {
+        let mut projects = sample();
+        let order = ListOrder {
+            key,
+            ascending,
+            group_by,
+        };
+        arrange(&mut projects, order, &roots());
+        projects
+    }
+
+    fn names(projects: &[ProjectInfo]) -> Vec<String> {
+        projects.iter().map(project_name).collect()
+    }
+
+    #[test]
+    fn sorts_by_each_key_in_both_directions() {
+        let cases: &[(SortKey, bool, [&str; 4])] = &[
+            (SortKey::Size, false, ["app", "loose", "beta", "zed"]),
+            (SortKey::Size, true, ["zed", "beta", "loose", "app"]),
+            // Oldest first when descending
+            (SortKey::Age, false, ["zed", "loose", "app", "beta"]),
+            (SortKey::Age, true, ["beta", "app", "loose", "zed"]),
+            (SortKey::Name, true, ["app", "beta", "loose", "zed"]),
+            (SortKey::Name, false, ["zed", "loose", "beta", "app"]),
+            (SortKey::Path, true, ["loose", "beta", "app", "zed"]),
+            (SortKey::Path, false, ["zed", "app", "beta", "loose"]),
+            // Equal kinds fall back to the path, whatever the direction
+            (SortKey::Kind, true, ["zed", "loose", "beta", "app"]),
+            (SortKey::Kind, false, ["app", "loose", "beta", "zed"]),
+        ];
+        for (key, ascending, expected) in cases {
+            assert_eq!(
+                names(&arranged(*key, *ascending, GroupBy::None)),
+                expected,
+                "{:?} ascending={}",
+                key,
+                ascending
+            );
+        }
+    }
+
+    #[test]
+    fn groups_by_root_with_an_unnamed_group_outside_the_roots() {
+        // Key, ascending, project order, then each group's path, range and total
+        type Case = (
+            SortKey,
+            bool,
+            [&'static str; 4],
+            [(&'static str, Range<usize>, u64); 3],
+        );
+        let cases: &[Case] = &[
+            // By size the groups follow their totals: /work 40, outside 25, /other 20
+            (
+                SortKey::Size,
+                false,
+                ["app", "zed", "loose", "beta"],
+                [("/work", 0..2, 40), ("", 2..3, 25), ("/other", 3..4, 20)],
+            ),
+            (
+                SortKey::Size,
+                true,
+                ["beta", "loose", "zed", "app"],
+                [("/other", 0..1, 20), ("", 1..2, 25), ("/work", 2..4, 40)],
+            ),
+            // Otherwise by path, which puts the unnamed group first
+            (
+                SortKey::Name,
+                true,
+                ["loose", "beta", "app", "zed"],
+                [("", 0..1, 25), ("/other", 1..2, 20), ("/work", 2..4, 40)],
+            ),
+            (
+                SortKey::Age,
+                false,
+                ["zed", "app", "beta", "loose"],
+                [("/work", 0..2, 40), ("/other", 2..3, 20), ("", 3..4, 25)],
+            ),
+        ];
+        for (key, ascending, expected, expected_groups) in cases {
+            let projects = arranged(*key, *ascending, GroupBy::Root);
+            assert_eq!(
+                names(&projects),
+                expected,
+                "{:?} ascending={}",
+                key,
+                ascending
+            );
+
+            let groups = groups(&projects, GroupBy::Root, &roots());
+            let found: Vec<(PathBuf, Range<usize>, u64)> = groups
+                .iter()
+                .map(|g| (g.path.clone(), g.range.clone(), g.disk_bytes))
+                .collect();
+            let expected_groups: Vec<(PathBuf, Range<usize>, u64)> = expected_groups
+                .iter()
+                .map(|(path, range, bytes)| (PathBuf::from(path), range.clone(), *bytes))
+                .collect();
+            assert_eq!(found, expected_groups, "{:?} ascending={}", key, ascending);
+
+            for group in &groups {
+                for project in &projects[group.range.clone()] {
+                    assert_eq!(GroupBy::Root.group_of(project, &roots()), group.path);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ungrouped_lists_are_one_group() {
+        let projects = arranged(SortKey::Size, false, GroupBy::None);
+        let groups = groups(&projects, GroupBy::None, &roots());
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].path, PathBuf::new());
+        assert_eq!(groups[0].range, 0..4);
+        assert_eq!(groups[0].disk_bytes, 85);
+        assert!(super::groups(&[], GroupBy::Root, &roots()).is_empty());
+    }
+}
@@ -1,10 +1,32 @@
 use gpui::prelude::*;
 use gpui::*;
 
+mod activity;
 mod app;
+mod bench;
+mod cancel;
+mod cleaner;
+mod cli;
 mod config;
+mod detectors;
+mod errors;
+mod filters;
+mod git;
+mod ignore;
+mod index;
+mod listing;
+mod mounts;
+mod patterns;
+mod preview;
+mod protect;
+mod quarantine;
+mod report;
+mod rules;
 mod scanner;
+mod trash;
 mod ui;
+mod usage;
+mod watcher;
 
 use app::StorageCleaner;
 
@@ -20,6 +42,12 @@ impl Render for StorageCleaner {
 }
 
 fn main() {
+    // Subcommands run headless without ever opening a window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if cli::is_headless(&args) {
+        std::process::exit(cli::run(&args));
+    }
+
     Application::new().run(|cx: &mut App| {
         cx.activate(true);
 
@@ -45,7 +73,7 @@ fn main() {
                 }),
                 ..Default::default()
             },
-            |_, cx| cx.new(|_| StorageCleaner::new()),
+            |_, cx| cx.new(StorageCleaner::new),
         )
         .unwrap();
     });
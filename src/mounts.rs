@@ -0,0 +1,155 @@
+//! Which filesystems the scanner should stay out of.
+//!
+//! Walking into an NFS or sshfs mount can stall a scan for minutes, and
+//! pseudo filesystems like `/proc` never hold build artifacts, so both are
+//! skipped by default. On Linux their mount points are read from
+//! `/proc/self/mountinfo`; elsewhere the table is empty and nothing is skipped
+//! by type.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// What a mounted filesystem is backed by, as far as the scanner cares
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MountKind {
+    Local,
+    /// Served over the network, slow or hanging when the server is away
+    Network,
+    /// Generated by the kernel, like `/proc` and `/sys`
+    Pseudo,
+}
+
+impl MountKind {
+    pub fn of(fs_type: &str) -> Self {
+        // FUSE mounts report `fuse.<helper>`, e.g. `fuse.sshfs`
+        let base = fs_type.strip_prefix("fuse.").unwrap_or(fs_type);
+        match base {
+            "nfs" | "nfs4" | "cifs" | "smb3" | "smbfs" | "ncpfs" | "afs" | "9p" | "ceph"
+            | "glusterfs" | "lustre" | "sshfs" | "rclone" | "s3fs" | "davfs2" | "gcsfuse" => {
+                MountKind::Network
+            }
+            "proc" | "sysfs" | "devtmpfs" | "devpts" | "cgroup" | "cgroup2" | "debugfs"
+            | "tracefs" | "securityfs" | "pstore" | "bpf" | "configfs" | "fusectl" | "mqueue"
+            | "hugetlbfs" | "autofs" | "binfmt_misc" | "efivarfs" | "rpc_pipefs" | "nsfs"
+            | "selinuxfs" => MountKind::Pseudo,
+            _ => MountKind::Local,
+        }
+    }
+}
+
+/// A directory the scanner did not enter because of what is mounted there
+#[derive(Clone, Debug)]
+pub struct SkippedMount {
+    pub path: PathBuf,
+    /// Filesystem type from the mount table, empty when it is not listed there
+    pub fs_type: String,
+    pub kind: MountKind,
+}
+
+impl fmt::Display for SkippedMount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.kind {
+            MountKind::Network => "network filesystem",
+            MountKind::Pseudo => "pseudo filesystem",
+            MountKind::Local => "other filesystem",
+        };
+        if self.fs_type.is_empty() {
+            write!(f, "{} ({})", self.path.display(), reason)
+        } else {
+            write!(f, "{} ({}, {})", self.path.display(), self.fs_type, reason)
+        }
+    }
+}
+
+/// Filesystem types by mount point
+#[derive(Clone, Debug, Default)]
+pub struct MountTable {
+    mounts: HashMap<PathBuf, String>,
+}
+
+impl MountTable {
+    /// Read the mounts visible to this process
+    pub fn load() -> Self {
+        #[cfg(target_os = "linux")]
+        {
+            match std::fs::read_to_string("/proc/self/mountinfo") {
+                Ok(contents) => Self::parse(&contents),
+                Err(_) => Self::default(),
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Self::default()
+        }
+    }
+
+    /// Parse `/proc/self/mountinfo`. Later lines win, since they are mounted
+    /// on top of earlier ones at the same point.
+    #[cfg(target_os = "linux")]
+    fn parse(contents: &str) -> Self {
+        let mounts = contents
+            .lines()
+            .filter_map(|line| {
+                // `36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw`
+                let (fields, rest) = line.split_once(" - ")?;
+                let mount_point = fields.split(' ').nth(4)?;
+                let fs_type = rest.split(' ').next()?;
+                Some((unescape(mount_point), fs_type.to_string()))
+            })
+            .collect();
+        Self { mounts }
+    }
+
+    /// Filesystem type mounted at `path`, if `path` is a mount point
+    pub fn fs_type(&self, path: &Path) -> Option<&str> {
+        self.mounts.get(path).map(String::as_str)
+    }
+}
+
+/// Undo the octal escapes mountinfo uses for spaces, tabs, newlines and `\`
+#[cfg(target_os = "linux")]
+fn unescape(field: &str) -> PathBuf {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::OsStringExt;
+
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let code = bytes.get(i + 1..i + 4).and_then(|digits| {
+            std::str::from_utf8(digits)
+                .ok()
+                .and_then(|digits| u8::from_str_radix(digits, 8).ok())
+        });
+        match code {
+            Some(byte) if bytes[i] == b'\\' => {
+                out.push(byte);
+                i += 4;
+            }
+            _ => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    PathBuf::from(OsString::from_vec(out))
+}
+
+/// Device the directory at `path` lives on. A symlink is on the device of
+/// its own directory unless `follow_symlinks` asks for its target's.
+#[cfg(unix)]
+pub fn device(path: &Path, follow_symlinks: bool) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = if follow_symlinks {
+        std::fs::metadata(path)
+    } else {
+        path.symlink_metadata()
+    };
+    metadata.ok().map(|m| m.dev())
+}
+
+#[cfg(not(unix))]
+pub fn device(_path: &Path, _follow_symlinks: bool) -> Option<u64> {
+    None
+}
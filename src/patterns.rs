@@ -0,0 +1,200 @@
+//! Gitignore-style rules for which directories the scanner enters.
+//!
+//! Each pattern excludes the directories it matches and a leading `!`
+//! re-includes them; the last matching pattern wins. A pattern without a `/`
+//! matches a directory name at any depth, one with a `/` is anchored to the
+//! scan root. `*` stays within one path component and `**` spans several.
+//! As in git, nothing below an excluded directory can be re-included, since
+//! the scanner never looks inside it.
+
+use globset::{GlobBuilder, GlobMatcher};
+use std::path::{Path, PathBuf};
+
+/// The built-in behaviour: skip hidden directories other than the hidden
+/// artifacts, and the macOS `Library` and `System` folders
+pub fn default_patterns() -> Vec<String> {
+    [
+        ".*", "!.venv", "!.next", "!.nuxt", "!.gradle", "Library", "System",
+    ]
+    .iter()
+    .map(|p| p.to_string())
+    .collect()
+}
+
+#[derive(Clone, Debug)]
+struct Rule {
+    matcher: GlobMatcher,
+    negated: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct PathFilter {
+    rules: Vec<Rule>,
+    /// Anchored patterns are relative to whichever of these contains the path
+    roots: Vec<PathBuf>,
+}
+
+impl Default for PathFilter {
+    fn default() -> Self {
+        Self::new(&default_patterns(), Vec::new()).unwrap_or(Self {
+            rules: Vec::new(),
+            roots: Vec::new(),
+        })
+    }
+}
+
+impl PathFilter {
+    /// Compile `patterns`, failing with a message naming the first invalid one
+    pub fn new(patterns: &[String], roots: Vec<PathBuf>) -> Result<Self, String> {
+        let rules = patterns
+            .iter()
+            .filter_map(|pattern| {
+                let pattern = pattern.trim();
+                (!pattern.is_empty() && !pattern.starts_with('#')).then_some(pattern)
+            })
+            .map(compile)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { rules, roots })
+    }
+
+    /// Whether the scanner should stay out of the directory at `path`
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.decide(path).unwrap_or(false)
+    }
+
+    /// `Some(true)` if the last matching pattern excludes `path`, `Some(false)`
+    /// if it re-includes it, `None` if no pattern matches
+    pub fn decide(&self, path: &Path) -> Option<bool> {
+        let relative = self
+            .roots
+            .iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .and_then(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path);
+        // Outside every root, strip `/` so unanchored patterns still match
+        let relative = relative.strip_prefix("/").unwrap_or(relative);
+
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matcher.is_match(relative))
+            .map(|rule| !rule.negated)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// Check a single pattern, for validating input before saving it
+pub fn validate(pattern: &str) -> Result<(), String> {
+    compile(pattern.trim()).map(|_| ())
+}
+
+fn compile(pattern: &str) -> Result<Rule, String> {
+    let (negated, body) = match pattern.strip_prefix('!') {
+        Some(body) => (true, body),
+        None => (false, pattern),
+    };
+    // Only directories are ever matched, so a trailing `/` changes nothing
+    let body = body.trim_end_matches('/');
+    let glob = match body.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if body.contains('/') => body.to_string(),
+        None => format!("**/{}", body),
+    };
+
+    let matcher = GlobBuilder::new(&glob)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| format!("invalid pattern '{}': {}", pattern, e))?
+        .compile_matcher();
+    Ok(Rule { matcher, negated })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(patterns: &[&str]) -> PathFilter {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        PathFilter::new(&patterns, vec![PathBuf::from("/work")]).unwrap()
+    }
+
+    #[test]
+    fn decides_by_the_last_matching_pattern() {
+        let cases: &[(&[&str], &str, Option<bool>)] = &[
+            (&["vendor"], "/work/vendor", Some(true)),
+            (&["vendor"], "/work/app/vendor", Some(true)),
+            (&["vendor"], "/work/vendored", None),
+            (&["vendor/"], "/work/app/vendor", Some(true)),
+            // Negation re-includes, and order decides
+            (&[".*", "!.venv"], "/work/app/.venv", Some(false)),
+            (&[".*", "!.venv"], "/work/app/.cache", Some(true)),
+            (&["!.venv", ".*"], "/work/app/.venv", Some(true)),
+            (&["build", "!build", "build"], "/work/build", Some(true)),
+            (&["build", "build", "!build"], "/work/build", Some(false)),
+            // A `/` anchors the pattern to the root
+            (&["/build"], "/work/build", Some(true)),
+            (&["/build"], "/work/app/build", None),
+            (&["app/build"], "/work/app/build", Some(true)),
+            (&["app/build"], "/work/x/app/build", None),
+            // `*` stays within a component, `**` spans several
+            (&["app/*"], "/work/app/cache", Some(true)),
+            (&["app/*"], "/work/app/cache/deep", None),
+            (&["app/**/cache"], "/work/app/a/b/cache", Some(true)),
+            (&["app/**/cache"], "/work/app/cache", Some(true)),
+            // Comments and blank lines are skipped
+            (&["# build", "", "  "], "/work/build", None),
+            // Outside every root, unanchored patterns still match
+            (&["vendor"], "/elsewhere/vendor", Some(true)),
+            (&[], "/work/build", None),
+        ];
+        for (patterns, path, expected) in cases {
+            assert_eq!(
+                filter(patterns).decide(Path::new(path)),
+                *expected,
+                "{:?} on {}",
+                patterns,
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn anchors_to_the_deepest_root() {
+        let patterns = vec!["/build".to_string()];
+        let roots = vec![PathBuf::from("/work"), PathBuf::from("/work/app")];
+        let filter = PathFilter::new(&patterns, roots).unwrap();
+        assert!(filter.is_excluded(Path::new("/work/app/build")));
+        assert!(filter.is_excluded(Path::new("/work/build")));
+        assert!(!filter.is_excluded(Path::new("/work/lib/build")));
+    }
+
+    #[test]
+    fn default_patterns_skip_hidden_directories_but_not_artifacts() {
+        let filter = PathFilter::new(&default_patterns(), vec![PathBuf::from("/work")]).unwrap();
+        for (path, excluded) in [
+            ("/work/.git", true),
+            ("/work/app/.cache", true),
+            ("/work/app/.venv", false),
+            ("/work/app/.next", false),
+            ("/work/app/.gradle", false),
+            ("/work/Library", true),
+            ("/work/app/src", false),
+        ] {
+            assert_eq!(filter.is_excluded(Path::new(path)), excluded, "{}", path);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        assert!(validate("[abc").is_err());
+        assert!(validate("!{a,b").is_err());
+        assert!(validate("!target").is_ok());
+        let patterns = vec!["ok".to_string(), "[bad".to_string()];
+        let error = PathFilter::new(&patterns, Vec::new()).unwrap_err();
+        assert!(error.contains("[bad"), "{}", error);
+    }
+}
@@ -0,0 +1,236 @@
+//! Dry run of a bulk delete: walks every selected artifact and reports what
+//! would be removed, without touching disk.
+
+use crate::cleaner::{DeleteMode, Hold, Holds};
+use crate::scanner::ProjectInfo;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Findings kept per artifact; a tree full of read-only directories would
+/// otherwise list thousands
+const MAX_FINDINGS: usize = 20;
+
+/// Something about an artifact worth a second look before removing it
+#[derive(Clone, Debug)]
+pub enum Finding {
+    /// A symlink whose target lies outside the artifact. The link itself is
+    /// removed, the target is left alone.
+    SymlinkOutside { link: PathBuf, target: PathBuf },
+    /// A read-only directory, whose entries cannot be removed
+    ReadOnly(PathBuf),
+    /// Another filesystem is mounted inside the artifact
+    MountPoint(PathBuf),
+    /// An entry that could not be inspected
+    Unreadable { path: PathBuf, message: String },
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::SymlinkOutside { link, target } => write!(
+                f,
+                "symlink points outside: {} -> {}",
+                link.display(),
+                target.display()
+            ),
+            Finding::ReadOnly(path) => write!(f, "read-only: {}", path.display()),
+            Finding::MountPoint(path) => write!(f, "mount point: {}", path.display()),
+            Finding::Unreadable { path, message } => {
+                write!(f, "unreadable: {} ({})", path.display(), message)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArtifactPreview {
+    pub artifact_path: PathBuf,
+    pub file_count: u64,
+    pub dir_count: u64,
+    /// Space freed on disk, as measured by the scan
+    pub total_bytes: u64,
+    /// The first [`MAX_FINDINGS`] findings
+    pub findings: Vec<Finding>,
+    /// Findings past the first [`MAX_FINDINGS`]
+    pub omitted_findings: usize,
+}
+
+impl ArtifactPreview {
+    fn push(&mut self, finding: Finding) {
+        if self.findings.len() < MAX_FINDINGS {
+            self.findings.push(finding);
+        } else {
+            self.omitted_findings += 1;
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DryRunReport {
+    pub mode: DeleteMode,
+    pub artifacts: Vec<ArtifactPreview>,
+    /// Selected artifacts a delete would leave alone, see [`Hold`]
+    pub protected_count: usize,
+    pub ignored_count: usize,
+    /// Why a delete would remove nothing, see [`CleanSummary::refused`]
+    ///
+    /// [`CleanSummary::refused`]: crate::cleaner::CleanSummary::refused
+    pub refused: Option<String>,
+}
+
+impl DryRunReport {
+    pub fn file_count(&self) -> u64 {
+        self.artifacts.iter().map(|a| a.file_count).sum()
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.artifacts.iter().map(|a| a.total_bytes).sum()
+    }
+
+    pub fn finding_count(&self) -> usize {
+        self.artifacts
+            .iter()
+            .map(|a| a.findings.len() + a.omitted_findings)
+            .sum()
+    }
+
+    pub fn status_message(&self) -> String {
+        if let Some(reason) = &self.refused {
+            return format!("❌ Dry run: a delete would remove nothing: {}", reason);
+        }
+        let verb = match self.mode {
+            DeleteMode::Trash => "move to trash",
+            DeleteMode::Quarantine => "quarantine",
+            DeleteMode::Permanent => "permanently delete",
+        };
+        let mut message = format!(
+            "🔍 Dry run: would {} {} artifact(s), {} file(s), {:.2} GB - {} warning(s)",
+            verb,
+            self.artifacts.len(),
+            self.file_count(),
+            self.total_bytes() as f64 / (1024.0 * 1024.0 * 1024.0),
+            self.finding_count()
+        );
+        if self.protected_count > 0 {
+            message.push_str(&format!(", 📌 {} protected", self.protected_count));
+        }
+        if self.ignored_count > 0 {
+            message.push_str(&format!(
+                ", 🚫 {} ignored by .devstorageignore or a rule",
+                self.ignored_count
+            ));
+        }
+        message
+    }
+}
+
+/// Inspect every selected project's artifact without modifying anything.
+/// Artifacts a delete would leave alone are only counted, and nothing is
+/// inspected when a delete would be refused.
+pub fn preview_selected(projects: &[ProjectInfo], mode: DeleteMode) -> DryRunReport {
+    let mut report = DryRunReport {
+        mode,
+        artifacts: Vec::new(),
+        protected_count: 0,
+        ignored_count: 0,
+        refused: None,
+    };
+    let holds = match Holds::load() {
+        Ok(holds) => holds,
+        Err(e) => {
+            report.refused = Some(e);
+            return report;
+        }
+    };
+    for project in projects.iter().filter(|p| p.selected) {
+        match holds.check(project) {
+            Some(Hold::Protected) => report.protected_count += 1,
+            Some(Hold::Ignored) => report.ignored_count += 1,
+            None => report.artifacts.push(preview_artifact(project)),
+        }
+    }
+    report
+}
+
+fn preview_artifact(project: &ProjectInfo) -> ArtifactPreview {
+    let root = project.artifact_path.as_path();
+    let mut preview = ArtifactPreview {
+        artifact_path: root.to_path_buf(),
+        file_count: 0,
+        dir_count: 0,
+        total_bytes: project.disk_bytes,
+        findings: Vec::new(),
+        omitted_findings: 0,
+    };
+    let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let root_device = device_of(root);
+
+    let mut walker = WalkDir::new(root).follow_links(false).into_iter();
+    while let Some(entry) = walker.next() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                preview.push(Finding::Unreadable {
+                    path: e.path().unwrap_or(root).to_path_buf(),
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let path = entry.path();
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                preview.push(Finding::Unreadable {
+                    path: path.to_path_buf(),
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        // A read-only file is removed like any other, but nothing inside a
+        // read-only directory can be
+        if metadata.is_dir() && metadata.permissions().readonly() {
+            preview.push(Finding::ReadOnly(path.to_path_buf()));
+        }
+
+        if entry.file_type().is_symlink() {
+            preview.file_count += 1;
+            if let Ok(target) = fs::read_link(path) {
+                let resolved = path.parent().unwrap_or(root).join(&target);
+                let resolved = resolved.canonicalize().unwrap_or(resolved);
+                if !resolved.starts_with(&canonical_root) {
+                    preview.push(Finding::SymlinkOutside {
+                        link: path.to_path_buf(),
+                        target,
+                    });
+                }
+            }
+        } else if entry.file_type().is_dir() {
+            if entry.depth() > 0 && root_device.is_some() && device_of(path) != root_device {
+                preview.push(Finding::MountPoint(path.to_path_buf()));
+                walker.skip_current_dir();
+                continue;
+            }
+            preview.dir_count += 1;
+        } else {
+            preview.file_count += 1;
+        }
+    }
+
+    preview
+}
+
+#[cfg(unix)]
+fn device_of(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    fs::symlink_metadata(path).ok().map(|m| m.dev())
+}
+
+#[cfg(not(unix))]
+fn device_of(_path: &Path) -> Option<u64> {
+    None
+}
@@ -0,0 +1,166 @@
+//! Projects that are never cleaned.
+//!
+//! Entries in the config's `protected` list are either paths or, when they
+//! contain `*`, `?`, `[` or `{`, globs in the same syntax as `scan_patterns`.
+//! A project is protected when its project or artifact directory, or any
+//! directory above them, is listed or matched, so protecting a folder
+//! covers every project inside it.
+
+use crate::config::Config;
+use crate::scanner::ProjectInfo;
+use globset::{GlobBuilder, GlobMatcher};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Default)]
+pub struct ProtectList {
+    paths: Vec<PathBuf>,
+    globs: Vec<GlobMatcher>,
+    /// Why each glob that does not compile was left out
+    invalid: Vec<String>,
+}
+
+impl ProtectList {
+    /// Compile `entries`. Globs that do not compile are left out and listed
+    /// by [`ProtectList::invalid`].
+    pub fn new(entries: &[String]) -> Self {
+        let mut list = Self::default();
+        for entry in entries.iter().map(|e| e.trim()).filter(|e| !e.is_empty()) {
+            if is_glob(entry) {
+                match compile(entry) {
+                    Ok(glob) => list.globs.push(glob),
+                    Err(e) => list.invalid.push(e),
+                }
+            } else {
+                list.paths.push(expand_home(entry));
+            }
+        }
+        list
+    }
+
+    /// The list as currently saved, for checking right before deleting.
+    /// Fails when the config cannot be read or an entry does not compile,
+    /// since either would leave projects unprotected.
+    pub fn load() -> Result<Self, String> {
+        let list = Self::new(&Config::try_load()?.protected);
+        if list.invalid.is_empty() {
+            Ok(list)
+        } else {
+            Err(format!(
+                "{} in the config's protected list",
+                list.invalid.join("; ")
+            ))
+        }
+    }
+
+    /// Entries that do not compile, with why
+    pub fn invalid(&self) -> &[String] {
+        &self.invalid
+    }
+
+    pub fn protects(&self, project: &ProjectInfo) -> bool {
+        self.protects_path(&project.artifact_path)
+    }
+
+    /// Whether `path` or any directory above it is protected
+    pub fn protects_path(&self, path: &Path) -> bool {
+        path.ancestors().any(|dir| {
+            self.paths.iter().any(|protected| protected == dir)
+                || self.globs.iter().any(|glob| glob.is_match(dir))
+        })
+    }
+}
+
+fn is_glob(entry: &str) -> bool {
+    entry.contains(['*', '?', '[', '{'])
+}
+
+/// Compile a path or glob entry, with a leading `~` expanded
+pub fn compile(entry: &str) -> Result<GlobMatcher, String> {
+    let pattern = expand_home(entry.trim_end_matches('/'));
+    GlobBuilder::new(&pattern.to_string_lossy())
+        .literal_separator(true)
+        .build()
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| format!("invalid glob '{}': {}", entry, e))
+}
+
+/// The path an entry names, with a leading `~` expanded
+pub fn expand_home(entry: &str) -> PathBuf {
+    if let Some(rest) = entry.strip_prefix('~') {
+        if rest.is_empty() || rest.starts_with('/') {
+            if let Some(home) = dirs::home_dir() {
+                return home.join(rest.trim_start_matches('/'));
+            }
+        }
+    }
+    // Keep `/` itself
+    match entry.trim_end_matches('/') {
+        "" => PathBuf::from(entry),
+        trimmed => PathBuf::from(trimmed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(entries: &[&str]) -> ProtectList {
+        let entries: Vec<String> = entries.iter().map(|e| e.to_string()).collect();
+        ProtectList::new(&entries)
+    }
+
+    #[test]
+    fn protects_listed_folders_and_everything_below() {
+        let cases: &[(&[&str], &str, bool)] = &[
+            // A path covers itself and what is inside, not its siblings
+            (&["/work/keep"], "/work/keep", true),
+            (&["/work/keep"], "/work/keep/app/node_modules", true),
+            (&["/work/keep"], "/work/keeper/node_modules", false),
+            (&["/work/keep/"], "/work/keep/node_modules", true),
+            (&["/work/keep"], "/work", false),
+            // A glob matches any folder above the artifact
+            (&["/work/client-*"], "/work/client-a/node_modules", true),
+            (&["/work/client-*"], "/work/clients/a/node_modules", false),
+            (&["/work/*/legacy"], "/work/a/legacy/target", true),
+            (&["/work/*/legacy"], "/work/a/b/legacy/target", false),
+            (&["/work/**/legacy"], "/work/a/b/legacy/target", true),
+            (&["/work/{a,b}"], "/work/b/dist", true),
+            // Blank entries protect nothing
+            (&["", "  "], "/work/keep", false),
+            (&[], "/work/keep", false),
+        ];
+        for (entries, path, expected) in cases {
+            assert_eq!(
+                list(entries).protects_path(Path::new(path)),
+                *expected,
+                "{:?} {}",
+                entries,
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn expands_home_in_paths_and_globs() {
+        let Some(home) = dirs::home_dir() else {
+            return;
+        };
+        assert!(list(&["~/keep"]).protects_path(&home.join("keep/node_modules")));
+        assert!(list(&["~/proj-*"]).protects_path(&home.join("proj-x/target")));
+        assert!(!list(&["~/keep"]).protects_path(Path::new("/elsewhere/keep")));
+        // Only a leading `~/` is the home directory
+        assert_eq!(expand_home("~user/keep"), PathBuf::from("~user/keep"));
+        assert_eq!(expand_home("/"), PathBuf::from("/"));
+    }
+
+    #[test]
+    fn reports_globs_that_do_not_compile() {
+        let protect = list(&["/work/[oops", "/work/keep", "/work/{a"]);
+        assert_eq!(protect.invalid().len(), 2);
+        assert!(protect.invalid()[0].contains("/work/[oops"));
+        assert!(protect.invalid()[1].contains("/work/{a"));
+        // The rest still apply
+        assert!(protect.protects_path(Path::new("/work/keep/dist")));
+        assert!(list(&["/work/keep"]).invalid().is_empty());
+    }
+}
@@ -0,0 +1,212 @@
+//! The app's own holding area for removed artifacts.
+//!
+//! Quarantined directories are moved under `<config dir>/quarantine` and
+//! recorded in `manifest.json` with their original path, size and time, so
+//! they can be restored until they expire and are purged.
+
+use crate::config::Config;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    /// Unique id, also the name of the directory holding the artifact
+    pub id: String,
+    pub original_path: PathBuf,
+    pub size_bytes: u64,
+    /// RFC 3339 timestamp in UTC
+    pub quarantined_at: String,
+}
+
+impl QuarantineEntry {
+    pub fn days_in_quarantine(&self) -> u64 {
+        DateTime::parse_from_rfc3339(&self.quarantined_at)
+            .map(|at| (Utc::now() - at.with_timezone(&Utc)).num_days().max(0) as u64)
+            .unwrap_or(0)
+    }
+
+    pub fn size_gb(&self) -> f64 {
+        self.size_bytes as f64 / (1024.0 * 1024.0 * 1024.0)
+    }
+}
+
+pub struct Quarantine {
+    dir: PathBuf,
+    entries: Vec<QuarantineEntry>,
+}
+
+impl Quarantine {
+    /// Open the quarantine in the config directory, creating it if needed
+    pub fn open() -> Self {
+        let dir = Config::config_dir().join("quarantine");
+        fs::create_dir_all(&dir).ok();
+
+        let entries = fs::read_to_string(dir.join("manifest.json"))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        Self { dir, entries }
+    }
+
+    pub fn entries(&self) -> &[QuarantineEntry] {
+        &self.entries
+    }
+
+    /// Move `path` into quarantine. If the manifest cannot be saved, `path`
+    /// is moved back and the error returned.
+    pub fn add(&mut self, path: &Path, size_bytes: u64) -> io::Result<()> {
+        let now = Utc::now();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "artifact".to_string());
+        let mut id = format!("{}-{}", now.timestamp_millis(), name);
+        let mut suffix = 2;
+        while self.dir.join(&id).exists() {
+            id = format!("{}-{}.{}", now.timestamp_millis(), name, suffix);
+            suffix += 1;
+        }
+
+        let target = self.dir.join(&id);
+        move_dir(path, &target)?;
+
+        self.entries.push(QuarantineEntry {
+            id,
+            original_path: path.to_path_buf(),
+            size_bytes,
+            quarantined_at: now.to_rfc3339(),
+        });
+        if let Err(e) = self.save() {
+            // Without a manifest entry it could never be restored, so put it back
+            self.entries.pop();
+            return match move_dir(&target, path) {
+                Ok(()) => Err(e),
+                Err(back) => Err(io::Error::new(
+                    e.kind(),
+                    format!(
+                        "{}, and moving it back failed ({}), it is left in {}",
+                        e,
+                        back,
+                        target.display()
+                    ),
+                )),
+            };
+        }
+        Ok(())
+    }
+
+    /// Move a quarantined artifact back to where it came from
+    pub fn restore(&mut self, id: &str) -> io::Result<PathBuf> {
+        let index = self
+            .entries
+            .iter()
+            .position(|e| e.id == id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not in quarantine"))?;
+        let original = self.entries[index].original_path.clone();
+
+        if original.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", original.display()),
+            ));
+        }
+        if let Some(parent) = original.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        move_dir(&self.dir.join(id), &original)?;
+        self.entries.remove(index);
+        self.save()?;
+        Ok(original)
+    }
+
+    /// Permanently delete entries quarantined at least `days` ago.
+    ///
+    /// Returns the number of entries purged and the bytes they held.
+    pub fn purge_older_than(&mut self, days: u32) -> (usize, u64) {
+        let mut purged = 0;
+        let mut purged_bytes = 0;
+        let dir = self.dir.clone();
+
+        self.entries.retain(|entry| {
+            if entry.days_in_quarantine() < days as u64 {
+                return true;
+            }
+            let stored = dir.join(&entry.id);
+            match fs::remove_dir_all(&stored) {
+                Ok(_) => {
+                    purged += 1;
+                    purged_bytes += entry.size_bytes;
+                    false
+                }
+                // Already gone from disk, so just forget it
+                Err(e) if e.kind() == io::ErrorKind::NotFound => false,
+                Err(e) => {
+                    eprintln!("Failed to purge {}: {}", stored.display(), e);
+                    true
+                }
+            }
+        });
+
+        if let Err(e) = self.save() {
+            eprintln!("Failed to save quarantine manifest: {}", e);
+        }
+        (purged, purged_bytes)
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(&self.entries)?;
+        fs::write(self.dir.join("manifest.json"), contents)
+    }
+}
+
+/// Rename a directory, falling back to copy and delete across filesystems
+fn move_dir(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(e) = copy_dir_all(from, to) {
+                fs::remove_dir_all(to).ok();
+                return Err(e);
+            }
+            fs::remove_dir_all(from)
+        }
+        result => result,
+    }
+}
+
+fn copy_dir_all(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let target = to.join(entry.file_name());
+
+        if file_type.is_symlink() {
+            copy_symlink(&entry.path(), &target)?;
+        } else if file_type.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(windows)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    let link_target = fs::read_link(from)?;
+    if fs::metadata(from).map(|m| m.is_dir()).unwrap_or(false) {
+        std::os::windows::fs::symlink_dir(link_target, to)
+    } else {
+        std::os::windows::fs::symlink_file(link_target, to)
+    }
+}
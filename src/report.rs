@@ -0,0 +1,162 @@
+use crate::scanner::ProjectInfo;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+    Ndjson,
+}
+
+impl ReportFormat {
+    /// Guess the format from a file extension, e.g. when saving from the UI
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .and_then(|e| e.parse().ok())
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            "ndjson" | "jsonl" => Ok(ReportFormat::Ndjson),
+            other => Err(format!("unknown report format '{}'", other)),
+        }
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+            ReportFormat::Ndjson => "ndjson",
+        })
+    }
+}
+
+/// One row of a scan report
+#[derive(Debug, Serialize)]
+pub struct ReportEntry {
+    pub project_path: String,
+    pub artifact_path: String,
+    pub kind: &'static str,
+    pub marker: String,
+    /// RFC 3339 timestamp in UTC
+    pub last_modified: String,
+    /// RFC 3339 timestamp in UTC of the project's last activity
+    pub last_active: String,
+    /// Which signal `last_active` came from
+    pub activity: String,
+    pub age_days: u64,
+    pub size_bytes: u64,
+    pub disk_bytes: u64,
+    /// Branch checked out in the project's repository, if it has one
+    pub git_branch: Option<String>,
+    /// Uncommitted changes in the project's repository
+    pub git_dirty: Option<bool>,
+    /// Commits or stashes that exist only in this checkout
+    pub git_unpushed: Option<bool>,
+    /// Name of the cleanup rule that decided the project, if any
+    pub rule: Option<String>,
+    pub selected: bool,
+}
+
+impl From<&ProjectInfo> for ReportEntry {
+    fn from(project: &ProjectInfo) -> Self {
+        Self {
+            project_path: project.project_path.display().to_string(),
+            artifact_path: project.artifact_path.display().to_string(),
+            kind: project.kind.id(),
+            marker: project.marker.clone(),
+            last_modified: DateTime::<Utc>::from(project.last_modified).to_rfc3339(),
+            last_active: DateTime::<Utc>::from(project.last_active).to_rfc3339(),
+            activity: project.activity.to_string(),
+            age_days: project.days_old(),
+            size_bytes: project.size_bytes,
+            disk_bytes: project.disk_bytes,
+            git_branch: project.git.as_ref().and_then(|git| git.branch.clone()),
+            git_dirty: project.git.as_ref().map(|git| git.is_dirty()),
+            git_unpushed: project
+                .git
+                .as_ref()
+                .map(|git| git.unpushed || git.stashes > 0),
+            rule: project.rule.as_ref().map(|rule| rule.name.clone()),
+            selected: project.selected,
+        }
+    }
+}
+
+const CSV_HEADER: &str =
+    "project_path,artifact_path,kind,marker,last_modified,last_active,activity,age_days,size_bytes,disk_bytes,git_branch,git_dirty,git_unpushed,rule,selected";
+
+pub fn write_report<W: Write>(
+    projects: &[ProjectInfo],
+    format: ReportFormat,
+    mut writer: W,
+) -> io::Result<()> {
+    let entries: Vec<ReportEntry> = projects.iter().map(ReportEntry::from).collect();
+
+    match format {
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &entries)?;
+            writeln!(writer)?;
+        }
+        ReportFormat::Ndjson => {
+            for entry in &entries {
+                serde_json::to_writer(&mut writer, entry)?;
+                writeln!(writer)?;
+            }
+        }
+        ReportFormat::Csv => {
+            writeln!(writer, "{}", CSV_HEADER)?;
+            for entry in &entries {
+                writeln!(
+                    writer,
+                    "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                    csv_field(&entry.project_path),
+                    csv_field(&entry.artifact_path),
+                    entry.kind,
+                    csv_field(&entry.marker),
+                    entry.last_modified,
+                    entry.last_active,
+                    entry.activity,
+                    entry.age_days,
+                    entry.size_bytes,
+                    entry.disk_bytes,
+                    csv_field(entry.git_branch.as_deref().unwrap_or("")),
+                    optional(entry.git_dirty),
+                    optional(entry.git_unpushed),
+                    csv_field(entry.rule.as_deref().unwrap_or("")),
+                    entry.selected
+                )?;
+            }
+        }
+    }
+
+    writer.flush()
+}
+
+/// An optional CSV field, empty when there is no value
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
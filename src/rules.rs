@@ -0,0 +1,296 @@
+//! Named cleanup rules from the config.
+//!
+//! Each rule lists conditions, all of which must hold, and an action. Rules
+//! are tried in order and the first one matching a project decides what
+//! happens to it: `select` marks it for cleaning, `protect` keeps it like a
+//! pinned project, and `ignore` leaves it out of the results. Conditions a
+//! rule does not mention match everything.
+
+use crate::activity;
+use crate::config::Config;
+use crate::detectors::ArtifactKind;
+use crate::protect;
+use crate::scanner::ProjectInfo;
+use globset::GlobMatcher;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// What happens to a project a rule matches
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    Select,
+    Protect,
+    Ignore,
+}
+
+impl RuleAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RuleAction::Select => "SELECT",
+            RuleAction::Protect => "PROTECT",
+            RuleAction::Ignore => "IGNORE",
+        }
+    }
+}
+
+impl fmt::Display for RuleAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RuleAction::Select => "select",
+            RuleAction::Protect => "protect",
+            RuleAction::Ignore => "ignore",
+        })
+    }
+}
+
+/// A rule as written in the config
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    pub name: String,
+    /// Artifact kinds the rule applies to, any kind when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kinds: Vec<ArtifactKind>,
+    /// Minimum days since the project was last active
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub older_than_days: Option<u32>,
+    /// Minimum size on disk of the artifact, in megabytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_size_mb: Option<u64>,
+    /// Path or glob of the project, or of a folder above it, in the syntax
+    /// of the `protected` list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// `true` for projects in a repository without local work, `false` for
+    /// projects with uncommitted, stashed or unpushed work. A project outside
+    /// any repository matches neither.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_clean: Option<bool>,
+    /// Whether a lockfile sits next to the artifact
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_lockfile: Option<bool>,
+    pub action: RuleAction,
+}
+
+/// The rule that decided a project
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleMatch {
+    pub name: String,
+    pub action: RuleAction,
+}
+
+#[derive(Debug)]
+struct CompiledRule {
+    rule: Rule,
+    path: Option<GlobMatcher>,
+}
+
+/// Rules ready to be matched against projects
+#[derive(Debug, Default)]
+pub struct RuleSet {
+    rules: Vec<CompiledRule>,
+}
+
+impl RuleSet {
+    /// Compile `rules`, failing on the first path that is not a valid glob
+    pub fn new(rules: &[Rule]) -> Result<Self, String> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let path = match rule.path.as_deref().map(str::trim) {
+                    Some(path) if !path.is_empty() => Some(
+                        protect::compile(path)
+                            .map_err(|e| format!("{} in rule '{}'", e, rule.name))?,
+                    ),
+                    _ => None,
+                };
+                Ok(CompiledRule {
+                    rule: rule.clone(),
+                    path,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { rules })
+    }
+
+    /// The rules as currently saved, failing when one of them does not
+    /// compile rather than leaving its protection out
+    pub fn load() -> Result<Self, String> {
+        Self::new(&Config::try_load()?.rules).map_err(|e| format!("{} in the config's rules", e))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The first rule matching `project`, if any
+    pub fn evaluate(&self, project: &ProjectInfo) -> Option<RuleMatch> {
+        self.rules
+            .iter()
+            .find(|compiled| compiled.matches(project))
+            .map(|compiled| RuleMatch {
+                name: compiled.rule.name.clone(),
+                action: compiled.rule.action,
+            })
+    }
+
+    /// The action of the first rule matching `project`, if any
+    pub fn action_for(&self, project: &ProjectInfo) -> Option<RuleAction> {
+        self.evaluate(project).map(|matched| matched.action)
+    }
+}
+
+impl CompiledRule {
+    fn matches(&self, project: &ProjectInfo) -> bool {
+        let rule = &self.rule;
+        if !rule.kinds.is_empty() && !rule.kinds.contains(&project.kind) {
+            return false;
+        }
+        if rule
+            .older_than_days
+            .is_some_and(|days| project.days_old() < days as u64)
+        {
+            return false;
+        }
+        if rule
+            .min_size_mb
+            .is_some_and(|mb| project.disk_bytes < mb.saturating_mul(1024 * 1024))
+        {
+            return false;
+        }
+        if let Some(glob) = &self.path {
+            if !project
+                .project_path
+                .ancestors()
+                .any(|dir| glob.is_match(dir))
+            {
+                return false;
+            }
+        }
+        if let Some(clean) = rule.git_clean {
+            match &project.git {
+                Some(git) if git.has_local_work() != clean => {}
+                _ => return false,
+            }
+        }
+        // Checked last since it is the only condition that touches disk
+        if let Some(lockfile) = rule.has_lockfile {
+            if activity::has_lockfile(&project.project_path) != lockfile {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activity::ActivitySignal;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    fn project(artifact: &str, kind: ArtifactKind, days_old: u64) -> ProjectInfo {
+        let artifact_path = PathBuf::from(artifact);
+        let last_active = SystemTime::now() - Duration::from_secs(days_old * 86400);
+        ProjectInfo {
+            project_path: artifact_path.parent().unwrap().to_path_buf(),
+            artifact_path,
+            kind,
+            marker: String::new(),
+            last_modified: last_active,
+            last_active,
+            activity: ActivitySignal::Artifact,
+            git: None,
+            rule: None,
+            size_bytes: 0,
+            disk_bytes: 0,
+            selected: false,
+        }
+    }
+
+    fn rule(name: &str, action: RuleAction) -> Rule {
+        Rule {
+            name: name.to_string(),
+            kinds: Vec::new(),
+            older_than_days: None,
+            min_size_mb: None,
+            path: None,
+            git_clean: None,
+            has_lockfile: None,
+            action,
+        }
+    }
+
+    fn decided(rules: &RuleSet, project: &ProjectInfo) -> Option<String> {
+        rules.evaluate(project).map(|matched| matched.name)
+    }
+
+    #[test]
+    fn first_matching_rule_decides() {
+        let keep = Rule {
+            path: Some("/work/keep".to_string()),
+            ..rule("keep", RuleAction::Protect)
+        };
+        let old_modules = Rule {
+            kinds: vec![ArtifactKind::NodeModules],
+            older_than_days: Some(30),
+            ..rule("old modules", RuleAction::Select)
+        };
+        let caches = Rule {
+            path: Some("/work/*-cache".to_string()),
+            ..rule("caches", RuleAction::Ignore)
+        };
+        let rules = RuleSet::new(&[keep.clone(), old_modules.clone(), caches]).unwrap();
+
+        let kept = project("/work/keep/app/node_modules", ArtifactKind::NodeModules, 90);
+        let old = project("/work/app/node_modules", ArtifactKind::NodeModules, 90);
+        let recent = project("/work/app/node_modules", ArtifactKind::NodeModules, 3);
+        let target = project("/work/app/target", ArtifactKind::CargoTarget, 90);
+        let cached = project("/work/x-cache/dist", ArtifactKind::Dist, 1);
+        assert_eq!(decided(&rules, &kept).as_deref(), Some("keep"));
+        assert_eq!(rules.action_for(&kept), Some(RuleAction::Protect));
+        assert_eq!(decided(&rules, &old).as_deref(), Some("old modules"));
+        assert_eq!(decided(&rules, &recent), None);
+        assert_eq!(decided(&rules, &target), None);
+        assert_eq!(rules.action_for(&cached), Some(RuleAction::Ignore));
+
+        // A select rule listed first wins over the protect rule after it
+        let rules = RuleSet::new(&[old_modules, keep]).unwrap();
+        assert_eq!(rules.action_for(&kept), Some(RuleAction::Select));
+    }
+
+    #[test]
+    fn path_conditions_expand_home() {
+        let Some(home) = dirs::home_dir() else {
+            return;
+        };
+        let mine = Rule {
+            path: Some("~/keep".to_string()),
+            ..rule("mine", RuleAction::Protect)
+        };
+        let rules = RuleSet::new(&[mine]).unwrap();
+        let inside = home.join("keep/app/node_modules");
+        let project = project(&inside.to_string_lossy(), ArtifactKind::NodeModules, 0);
+        assert_eq!(rules.action_for(&project), Some(RuleAction::Protect));
+    }
+
+    #[test]
+    fn rejects_paths_that_do_not_compile() {
+        let broken = Rule {
+            path: Some("/work/[oops".to_string()),
+            ..rule("broken", RuleAction::Protect)
+        };
+        let error = RuleSet::new(&[rule("fine", RuleAction::Select), broken]).unwrap_err();
+        assert!(error.contains("/work/[oops"), "{}", error);
+        assert!(error.contains("rule 'broken'"), "{}", error);
+        // Blank paths are no condition at all
+        let blank = Rule {
+            path: Some("  ".to_string()),
+            ..rule("blank", RuleAction::Select)
+        };
+        let rules = RuleSet::new(&[blank]).unwrap();
+        let anywhere = project("/anywhere/node_modules", ArtifactKind::NodeModules, 0);
+        assert_eq!(rules.action_for(&anywhere), Some(RuleAction::Select));
+    }
+}
@@ -1,151 +1,754 @@
+use crate::activity::{self, ActivitySignal};
+use crate::cancel::CancelToken;
+use crate::detectors::{self, ArtifactDetector, ArtifactKind, Detection};
+use crate::errors::{ErrorLog, ScanError};
+use crate::git::{self, GitState};
+use crate::ignore::IgnoreStack;
+use crate::index::{self, ScanIndex};
+use crate::mounts::{self, MountKind, MountTable, SkippedMount};
+use crate::patterns::PathFilter;
+use crate::rules::{RuleAction, RuleMatch};
+use crate::usage;
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::time::SystemTime;
-use walkdir::WalkDir;
 
 #[derive(Clone, Debug)]
 pub struct ProjectInfo {
     pub project_path: PathBuf,
-    pub node_modules_path: PathBuf,
+    pub artifact_path: PathBuf,
+    pub kind: ArtifactKind,
+    /// Marker file that confirmed the artifact, e.g. `Cargo.toml`
+    pub marker: String,
+    /// Modification time of the artifact directory
     pub last_modified: SystemTime,
-    pub size_mb: f64,
+    /// When the project was last worked on, which its age is measured from
+    pub last_active: SystemTime,
+    /// The signal `last_active` came from
+    pub activity: ActivitySignal,
+    /// State of the git repository holding the project, if there is one
+    pub git: Option<GitState>,
+    /// The cleanup rule that decided the project, set by whoever applies rules
+    pub rule: Option<RuleMatch>,
+    /// Apparent size, each hardlinked file counted once
+    pub size_bytes: u64,
+    /// Allocated bytes that deleting the artifact would free
+    pub disk_bytes: u64,
     pub selected: bool,
 }
 
 impl ProjectInfo {
+    /// Days since the project was last active
     pub fn days_old(&self) -> u64 {
-        if let Ok(duration) = SystemTime::now().duration_since(self.last_modified) {
+        if let Ok(duration) = SystemTime::now().duration_since(self.last_active) {
             duration.as_secs() / 86400
         } else {
             0
         }
     }
 
+    /// Space reclaimed by deleting the artifact
     pub fn size_gb(&self) -> f64 {
-        self.size_mb / 1024.0
+        self.disk_bytes as f64 / (1024.0 * 1024.0 * 1024.0)
+    }
+
+    pub fn apparent_gb(&self) -> f64 {
+        self.size_bytes as f64 / (1024.0 * 1024.0 * 1024.0)
+    }
+
+    /// What the rule that decided the project does with it
+    pub fn rule_action(&self) -> Option<RuleAction> {
+        self.rule.as_ref().map(|rule| rule.action)
     }
 }
 
 pub struct Scanner {
     threshold_days: u32,
+    detectors: Vec<Box<dyn ArtifactDetector>>,
+    cancel: CancelToken,
+    threads: usize,
+    index: Option<Mutex<ScanIndex>>,
+    filter: PathFilter,
+    mounts: MountTable,
+    one_filesystem: bool,
+    network_mounts: bool,
+    skipped: Mutex<Vec<SkippedMount>>,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    /// Directories entered by any scan so far, tracked when following symlinks
+    /// so that loops end and nothing reached through two paths, or from two
+    /// scan roots, is listed twice
+    visited: Mutex<HashSet<DirId>>,
+    errors: ErrorLog,
+    activity: ActivitySignal,
+    /// Repository states by git directory, read once per scanner since many
+    /// artifacts can share a repository
+    git_states: Mutex<HashMap<PathBuf, GitState>>,
+}
+
+/// Shared state of one parallel walk
+struct Walk<'a> {
+    progress_callback: &'a (dyn Fn(&str, f32) + Sync),
+    found_callback: &'a (dyn Fn(&ProjectInfo) + Sync),
+    processed_count: AtomicUsize,
+    top_level_total: usize,
+    top_level_done: AtomicUsize,
+    /// Device of the scan path, set when staying on one filesystem
+    root_device: Option<u64>,
+    projects: Mutex<Vec<ProjectInfo>>,
+    /// Every artifact detected, including those younger than the threshold
+    detected: Mutex<HashSet<PathBuf>>,
+}
+
+/// Where a directory sits in the walk of the scan path containing it, which
+/// decides whether the depth and filesystem limits let the scanner in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WalkPosition {
+    /// Levels below the scan path
+    depth: usize,
+    /// Device of the scan path, set when staying on one filesystem
+    root_device: Option<u64>,
+}
+
+impl WalkPosition {
+    /// The position of a subdirectory
+    pub fn child(self) -> Self {
+        Self {
+            depth: self.depth + 1,
+            ..self
+        }
+    }
+}
+
+#[cfg(unix)]
+pub type DirId = (u64, u64);
+#[cfg(not(unix))]
+pub type DirId = PathBuf;
+
+/// What `path` resolves to, the same for every symlink leading there
+#[cfg(unix)]
+fn dir_id(path: &Path) -> Option<DirId> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|m| (m.dev(), m.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_id(path: &Path) -> Option<DirId> {
+    path.canonicalize().ok()
 }
 
 impl Scanner {
     pub fn new(threshold_days: u32) -> Self {
-        Self { threshold_days }
+        Self {
+            threshold_days,
+            detectors: detectors::builtin(),
+            cancel: CancelToken::new(),
+            threads: 0,
+            index: None,
+            filter: PathFilter::default(),
+            mounts: MountTable::load(),
+            one_filesystem: false,
+            network_mounts: false,
+            skipped: Mutex::new(Vec::new()),
+            max_depth: None,
+            follow_symlinks: false,
+            visited: Mutex::new(HashSet::new()),
+            errors: ErrorLog::default(),
+            activity: ActivitySignal::default(),
+            git_states: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Stop scanning once `cancel` is triggered, returning what was found so far
+    pub fn with_cancel_token(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Number of worker threads for walking and sizing, 0 for one per CPU
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Decide which directories to enter with `filter` instead of the defaults
+    pub fn with_filter(mut self, filter: PathFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Stay on the filesystem of the scan path, like `du -x`
+    pub fn with_one_filesystem(mut self, one_filesystem: bool) -> Self {
+        self.one_filesystem = one_filesystem;
+        self
+    }
+
+    /// Also walk into network mounts, which are skipped by default
+    pub fn with_network_mounts(mut self, network_mounts: bool) -> Self {
+        self.network_mounts = network_mounts;
+        self
+    }
+
+    /// Look at most `max_depth` levels below the scan path, `None` for no limit
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Enter symlinked directories. Each directory is still walked only once,
+    /// however many links lead to it.
+    pub fn with_follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Mount points left out by the scans so far
+    pub fn skipped_mounts(&self) -> Vec<SkippedMount> {
+        self.skipped
+            .lock()
+            .map(|skipped| skipped.clone())
+            .unwrap_or_default()
+    }
+
+    /// Measure how long projects have been idle by `signal`
+    pub fn with_activity_signal(mut self, signal: ActivitySignal) -> Self {
+        self.activity = signal;
+        self
+    }
+
+    /// Directories and files the scans so far could not read, and ignore
+    /// files they could not parse
+    pub fn errors(&self) -> Vec<ScanError> {
+        self.errors.snapshot()
+    }
+
+    /// Reuse sizes from `index` for unchanged artifacts and record fresh ones
+    /// into it. Take it back with [`Scanner::into_index`] after scanning.
+    pub fn with_index(mut self, index: ScanIndex) -> Self {
+        self.index = Some(Mutex::new(index));
+        self
+    }
+
+    pub fn into_index(self) -> Option<ScanIndex> {
+        self.index.and_then(|index| index.into_inner().ok())
+    }
+
+    #[allow(dead_code)]
+    pub fn with_detectors(mut self, detectors: Vec<Box<dyn ArtifactDetector>>) -> Self {
+        self.detectors = detectors;
+        self
+    }
+
+    fn detect(&self, path: &Path) -> Option<Detection> {
+        self.detectors.iter().find_map(|d| d.detect(path))
+    }
+
+    /// Whether any detector claims `path`, without sizing it
+    pub fn is_artifact(&self, path: &Path) -> bool {
+        self.detect(path).is_some()
     }
 
     pub fn scan(&self, scan_path: &Path) -> Vec<ProjectInfo> {
-        self.scan_with_progress(scan_path, |_folder, _progress| {})
+        self.scan_with_progress(scan_path, |_folder, _progress| {}, |_project| {})
     }
 
-    pub fn scan_with_progress<F>(
+    /// Scan `scan_path`, reporting the folder being visited with an estimated
+    /// progress between 0 and 1, and every artifact as soon as it is found.
+    ///
+    /// Directories are walked and sized in parallel, so both callbacks may be
+    /// called from several threads at once.
+    pub fn scan_with_progress<F, G>(
         &self,
         scan_path: &Path,
-        mut progress_callback: F,
+        progress_callback: F,
+        found_callback: G,
     ) -> Vec<ProjectInfo>
     where
-        F: FnMut(&str, f32),
+        F: Fn(&str, f32) + Sync,
+        G: Fn(&ProjectInfo) + Sync,
     {
-        let mut projects = Vec::new();
-        let mut processed_count = 0;
-
-        for entry in WalkDir::new(scan_path)
-            .follow_links(false)
-            .into_iter()
-            .filter_entry(|e| {
-                // Skip hidden directories and common non-project directories
-                if let Some(name) = e.file_name().to_str() {
-                    // Skip hidden files/dirs
-                    if name.starts_with('.') {
-                        return false;
-                    }
-                    // Skip system directories
-                    if name == "Library" || name == "System" {
-                        return false;
-                    }
-                }
-                true
+        let start = self.root_position(scan_path);
+        self.walk_from(scan_path, start, &progress_callback, &found_callback)
+    }
+
+    /// Scan `path`, a directory found at `position` after the scan of its
+    /// root, such as one created while the results are watched
+    pub fn scan_at(&self, path: &Path, position: WalkPosition) -> Vec<ProjectInfo> {
+        self.walk_from(path, position, &|_folder, _progress| {}, &|_project| {})
+    }
+
+    fn walk_from(
+        &self,
+        scan_path: &Path,
+        start: WalkPosition,
+        progress_callback: &(dyn Fn(&str, f32) + Sync),
+        found_callback: &(dyn Fn(&ProjectInfo) + Sync),
+    ) -> Vec<ProjectInfo> {
+        let Some(ignores) = self.ignores(scan_path) else {
+            return Vec::new();
+        };
+
+        // Progress is measured in top-level directories of the scan path, which
+        // are cheap to count up front and advance steadily over a projects folder
+        let top_level_total = fs::read_dir(scan_path)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+                    .count()
             })
-            .filter_map(|e| e.ok())
-        {
-            let path = entry.path();
+            .unwrap_or(0);
+
+        let walk = Walk {
+            progress_callback,
+            found_callback,
+            processed_count: AtomicUsize::new(0),
+            top_level_total,
+            top_level_done: AtomicUsize::new(0),
+            root_device: start.root_device,
+            projects: Mutex::new(Vec::new()),
+            detected: Mutex::new(HashSet::new()),
+        };
 
-            // Update progress periodically
-            processed_count += 1;
-            if processed_count % 10 == 0 {
-                let folder_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-                progress_callback(folder_name, 0.0); // We don't have total count, just report activity
+        match ThreadPoolBuilder::new().num_threads(self.threads).build() {
+            Ok(pool) => pool.install(|| self.visit(scan_path, start.depth, &ignores, &walk)),
+            Err(_) => self.visit(scan_path, start.depth, &ignores, &walk),
+        }
+
+        let mut projects = walk.projects.into_inner().unwrap_or_default();
+
+        // Only a complete walk proves that missing artifacts are gone
+        if !self.cancel.is_cancelled() {
+            let detected = walk.detected.into_inner().unwrap_or_default();
+            if let Some(Ok(mut index)) = self.index.as_ref().map(|index| index.lock()) {
+                index.prune(scan_path, &detected);
             }
+        }
+
+        // Sort by size (largest first)
+        projects.sort_by_key(|p| std::cmp::Reverse(p.disk_bytes));
+
+        projects
+    }
+
+    /// The position of the scan path `root` itself
+    pub fn root_position(&self, root: &Path) -> WalkPosition {
+        WalkPosition {
+            depth: 0,
+            // A scan path that is a link is walked through, so its target
+            // is what the rest of the tree is compared to
+            root_device: if self.one_filesystem {
+                mounts::device(root, true)
+            } else {
+                None
+            },
+        }
+    }
+
+    /// The position of `path` in the walk of `root`, `None` if it lies outside
+    pub fn position(&self, root: &Path, path: &Path) -> Option<WalkPosition> {
+        let depth = path.strip_prefix(root).ok()?.components().count();
+        Some(WalkPosition {
+            depth,
+            ..self.root_position(root)
+        })
+    }
+
+    /// Whether a walk reads the entries of a directory at `position`
+    pub fn reads_below(&self, position: WalkPosition) -> bool {
+        self.max_depth
+            .is_none_or(|max_depth| position.depth < max_depth)
+    }
+
+    /// Whether the limits, exclusion patterns and `.devstorageignore` files
+    /// let the scanner into `path` at `position`
+    pub fn should_enter(&self, path: &Path, position: WalkPosition) -> bool {
+        let parent = path.parent().and_then(|parent| self.ignores(parent));
+        parent.is_some_and(|ignores| self.enter_at(path, &ignores, position).is_some())
+    }
+
+    /// Like [`Scanner::enter`], but also keeps to the depth limit and, when
+    /// staying on one filesystem, to the device of the scan path
+    pub fn enter_at(
+        &self,
+        path: &Path,
+        ignores: &IgnoreStack,
+        position: WalkPosition,
+    ) -> Option<IgnoreStack> {
+        if self
+            .max_depth
+            .is_some_and(|max_depth| position.depth > max_depth)
+        {
+            return None;
+        }
+        if self.mount_skip(path, position.root_device).is_some() {
+            return None;
+        }
+        self.enter(path, ignores)
+    }
+
+    /// The `.devstorageignore` rules in effect inside `dir`, `None` if they
+    /// exclude it. Files that do not parse are noted in [`Scanner::errors`].
+    pub fn ignores(&self, dir: &Path) -> Option<IgnoreStack> {
+        IgnoreStack::for_dir(dir, &self.errors)
+    }
+
+    /// The ignore rules inside `path` if the scanner may enter it, given the
+    /// rules in effect in its parent
+    pub fn enter(&self, path: &Path, ignores: &IgnoreStack) -> Option<IgnoreStack> {
+        if self.mount_skip(path, None).is_some() {
+            return None;
+        }
+        let excluded = ignores
+            .decide(path)
+            .unwrap_or_else(|| self.filter.is_excluded(path));
+        if excluded {
+            return None;
+        }
+        ignores.descend(path, &self.errors)
+    }
+
+    /// The mount at `path`, if the scanner should stay out of it. Other local
+    /// filesystems are only left out when `root_device` is given.
+    fn mount_skip(&self, path: &Path, root_device: Option<u64>) -> Option<SkippedMount> {
+        let fs_type = self.mounts.fs_type(path);
+        let kind = fs_type.map(MountKind::of).unwrap_or(MountKind::Local);
+        let skip = match kind {
+            MountKind::Pseudo => true,
+            MountKind::Network => !self.network_mounts,
+            // Not every filesystem boundary is in the mount table, btrfs
+            // subvolumes for one, so compare devices
+            MountKind::Local => root_device.is_some_and(|root| {
+                mounts::device(path, self.follow_symlinks).is_some_and(|device| device != root)
+            }),
+        };
+        skip.then(|| SkippedMount {
+            path: path.to_path_buf(),
+            fs_type: fs_type.unwrap_or_default().to_string(),
+            kind,
+        })
+    }
+
+    fn visit(&self, dir: &Path, depth: usize, ignores: &IgnoreStack, walk: &Walk) {
+        if self.cancel.is_cancelled() {
+            return;
+        }
+        if self.follow_symlinks && !self.first_visit(dir) {
+            return;
+        }
+
+        // Update progress periodically, and whenever a new top-level directory starts
+        let processed_count = walk.processed_count.fetch_add(1, Ordering::Relaxed) + 1;
+        if depth == 1 {
+            walk.top_level_done.fetch_add(1, Ordering::Relaxed);
+        }
+        if processed_count.is_multiple_of(10) || depth == 1 {
+            let folder_name = dir.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            let progress = if walk.top_level_total > 0 {
+                let done = walk.top_level_done.load(Ordering::Relaxed);
+                (done as f32 / walk.top_level_total as f32).min(1.0)
+            } else {
+                0.0
+            };
+            (walk.progress_callback)(folder_name, progress);
+        }
+
+        // Never descend into a detected artifact. This prevents reporting nested
+        // artifacts like /project/node_modules/@esbuild-kit/core-utils/node_modules
+        if let Some(detection) = self.detect(dir) {
+            self.record(dir, detection, depth, walk);
+            return;
+        }
 
-            // Skip anything that's already inside a node_modules directory (check all ancestors)
-            // This prevents recursing into nested node_modules like:
-            // /project/node_modules/@esbuild-kit/core-utils/node_modules
-            // We still want to detect the top-level /project/node_modules
-            let mut has_node_modules_ancestor = false;
-            for ancestor in path.ancestors().skip(1) {
-                if ancestor.file_name() == Some(std::ffi::OsStr::new("node_modules")) {
-                    has_node_modules_ancestor = true;
-                    break;
+        if self.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            return;
+        }
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.errors.record(dir, "cannot read directory", &e);
+                return;
+            }
+        };
+        let subdirs: Vec<(PathBuf, IgnoreStack)> = entries
+            .filter_map(|entry| {
+                entry
+                    .map_err(|e| self.errors.record(dir, "cannot read directory entry", &e))
+                    .ok()
+            })
+            .filter(|e| self.is_subdir(e))
+            .map(|e| e.path())
+            .filter_map(|path| {
+                if let Some(skipped) = self.mount_skip(&path, walk.root_device) {
+                    if let Ok(mut list) = self.skipped.lock() {
+                        list.push(skipped);
+                    }
+                    return None;
                 }
+                let ignores = self.enter(&path, ignores)?;
+                Some((path, ignores))
+            })
+            .collect();
+
+        subdirs
+            .par_iter()
+            .for_each(|(subdir, ignores)| self.visit(subdir, depth + 1, ignores, walk));
+    }
+
+    /// Whether a walk goes into `entry`. DirEntry::file_type does not follow
+    /// symlinks, so linked directories are only entered when asked to.
+    pub fn is_subdir(&self, entry: &fs::DirEntry) -> bool {
+        match entry.file_type() {
+            Ok(t) if t.is_symlink() => {
+                self.follow_symlinks && fs::metadata(entry.path()).is_ok_and(|m| m.is_dir())
             }
-            if has_node_modules_ancestor {
+            Ok(t) => t.is_dir(),
+            Err(_) => false,
+        }
+    }
+
+    /// What `dir` resolves to when symlinks are followed, so a directory
+    /// reached through several links, or a loop, is only walked once
+    pub fn link_id(&self, dir: &Path) -> Option<DirId> {
+        if self.follow_symlinks {
+            dir_id(dir)
+        } else {
+            None
+        }
+    }
+
+    /// Claim `dir` for this scanner. False if it was already reached through
+    /// another path, or through a symlink loop.
+    fn first_visit(&self, dir: &Path) -> bool {
+        let Some(id) = self.link_id(dir) else {
+            return false;
+        };
+        self.visited
+            .lock()
+            .map(|mut visited| visited.insert(id))
+            .unwrap_or(false)
+    }
+
+    /// Detect and size a single directory at `position`, without walking below it.
+    ///
+    /// Returns `None` when `path` is not an artifact or is newer than the threshold.
+    pub fn scan_artifact(&self, path: &Path, position: WalkPosition) -> Option<ProjectInfo> {
+        self.ignores(path)?;
+        let detection = self.detect(path)?;
+        self.inspect(path, detection, position.depth, position.root_device)
+    }
+
+    fn record(&self, path: &Path, detection: Detection, depth: usize, walk: &Walk) {
+        if let Ok(mut detected) = walk.detected.lock() {
+            detected.insert(path.to_path_buf());
+        }
+        let Some(project) = self.inspect(path, detection, depth, walk.root_device) else {
+            return;
+        };
+        (walk.found_callback)(&project);
+        if let Ok(mut projects) = walk.projects.lock() {
+            projects.push(project);
+        }
+    }
+
+    fn git_state(&self, repo: &git::Repo) -> GitState {
+        let cached = self
+            .git_states
+            .lock()
+            .ok()
+            .and_then(|states| states.get(&repo.git_dir).cloned());
+        if let Some(state) = cached {
+            return state;
+        }
+        let state = git::read_state(repo, &|path| self.is_artifact(path));
+        if let Ok(mut states) = self.git_states.lock() {
+            states.insert(repo.git_dir.clone(), state.clone());
+        }
+        state
+    }
+
+    /// Newest file modification time in `dir` and below, skipping artifacts
+    /// and hidden directories like `.git`. Walks with the same exclusions,
+    /// mount checks and depth limit as the scan, `depth` being the level of
+    /// `dir` below the scan path. Symlinks are not followed.
+    fn newest_source(
+        &self,
+        dir: &Path,
+        depth: usize,
+        ignores: &IgnoreStack,
+        root_device: Option<u64>,
+    ) -> Option<SystemTime> {
+        if self.cancel.is_cancelled() || self.max_depth.is_some_and(|max| depth >= max) {
+            return None;
+        }
+        let mut newest = None;
+        let mut subdirs = Vec::new();
+        for entry in fs::read_dir(dir).ok()?.filter_map(|e| e.ok()) {
+            // DirEntry::metadata does not follow symlinks
+            let Ok(metadata) = entry.metadata() else {
                 continue;
+            };
+            if metadata.is_file() {
+                newest = newest.max(metadata.modified().ok());
+            } else if metadata.is_dir() {
+                let path = entry.path();
+                let hidden = entry.file_name().to_string_lossy().starts_with('.');
+                if hidden || self.is_artifact(&path) {
+                    continue;
+                }
+                if self.mount_skip(&path, root_device).is_some() {
+                    continue;
+                }
+                if let Some(ignores) = self.enter(&path, ignores) {
+                    subdirs.push((path, ignores));
+                }
             }
+        }
 
-            // Now check if this entry IS a node_modules directory at the project level
-            if path.file_name() == Some(std::ffi::OsStr::new("node_modules")) && path.is_dir() {
-                if let Some(project_path) = path.parent() {
-                    if let Ok(metadata) = fs::metadata(path) {
-                        if let Ok(modified) = metadata.modified() {
-                            let days_old =
-                                if let Ok(duration) = SystemTime::now().duration_since(modified) {
-                                    duration.as_secs() / 86400
-                                } else {
-                                    0
-                                };
-
-                            if days_old >= self.threshold_days as u64 {
-                                let size = Self::calculate_dir_size(path);
-                                let size_mb = size as f64 / (1024.0 * 1024.0);
-
-                                projects.push(ProjectInfo {
-                                    project_path: project_path.to_path_buf(),
-                                    node_modules_path: path.to_path_buf(),
-                                    last_modified: modified,
-                                    size_mb,
-                                    selected: false,
-                                });
-                            }
-                        }
-                    }
-                }
+        let below = subdirs
+            .par_iter()
+            .filter_map(|(subdir, ignores)| {
+                self.newest_source(subdir, depth + 1, ignores, root_device)
+            })
+            .max();
+        newest.max(below)
+    }
+
+    /// `depth` is the level of the artifact below the scan path
+    fn inspect(
+        &self,
+        path: &Path,
+        detection: Detection,
+        depth: usize,
+        root_device: Option<u64>,
+    ) -> Option<ProjectInfo> {
+        let project_path = path.parent()?;
+        let modified = match fs::metadata(path).and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            Err(e) => {
+                self.errors
+                    .record(path, "cannot read modification time", &e);
+                return None;
             }
+        };
+
+        // Not indexed: editing a source file changes nothing the artifact's
+        // fingerprint covers, so only a fresh walk sees it
+        let (last_active, signal) =
+            activity::last_active(self.activity, project_path, modified, &|project| {
+                let ignores = self.ignores(project)?;
+                self.newest_source(project, depth.saturating_sub(1), &ignores, root_device)
+            });
+        let days_old = if let Ok(duration) = SystemTime::now().duration_since(last_active) {
+            duration.as_secs() / 86400
+        } else {
+            0
+        };
+        if days_old < self.threshold_days as u64 {
+            return None;
         }
 
-        // Sort by size (largest first)
-        projects.sort_by(|a, b| {
-            b.size_mb
-                .partial_cmp(&a.size_mb)
-                .unwrap_or(std::cmp::Ordering::Equal)
+        let fingerprint = self.index.as_ref().and_then(|_| index::fingerprint(path));
+        let cached = fingerprint.and_then(|fingerprint| {
+            let index = self.index.as_ref()?.lock().ok()?;
+            index.lookup(path, fingerprint)
         });
+        let (usage, complete) = match cached {
+            Some(usage) => (usage, true),
+            None => {
+                let (usage, errors) = usage::measure(path, &self.cancel);
+                let complete = errors.is_empty();
+                self.errors.extend(errors);
+                (usage, complete)
+            }
+        };
+        // A size cut short by cancelling would be wrong, so drop it
+        if self.cancel.is_cancelled() {
+            return None;
+        }
 
-        projects
+        let project = ProjectInfo {
+            project_path: project_path.to_path_buf(),
+            artifact_path: path.to_path_buf(),
+            kind: detection.kind,
+            marker: detection.marker,
+            last_modified: modified,
+            last_active,
+            activity: signal,
+            git: git::find_repo(project_path).map(|repo| self.git_state(&repo)),
+            rule: None,
+            size_bytes: usage.apparent_bytes,
+            disk_bytes: usage.disk_bytes,
+            selected: false,
+        };
+        // A size missing unreadable parts is not worth remembering
+        if let (Some(index), Some(fingerprint), true) = (&self.index, fingerprint, complete) {
+            if let Ok(mut index) = index.lock() {
+                index.insert(&project, fingerprint);
+            }
+        }
+        Some(project)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::Duration;
+
+    const DAY: Duration = Duration::from_secs(86400);
+
+    fn set_modified(path: &Path, time: SystemTime) {
+        File::options()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_modified(time))
+            .unwrap();
     }
 
-    fn calculate_dir_size(path: &Path) -> u64 {
-        let mut size = 0u64;
-        if let Ok(entries) = fs::read_dir(path) {
-            for entry in entries.filter_map(|e| e.ok()) {
-                if let Ok(metadata) = entry.metadata() {
-                    if metadata.is_file() {
-                        size += metadata.len();
-                    } else if metadata.is_dir() {
-                        size += Self::calculate_dir_size(&entry.path());
-                    }
-                }
-            }
+    #[test]
+    fn rescan_with_index_sees_edited_sources() {
+        let root = std::env::temp_dir().join(format!("dsc-scanner-{}-sources", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let project = root.join("app");
+        fs::create_dir_all(project.join("node_modules/left-pad")).unwrap();
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(project.join("package.json"), "{}").unwrap();
+        fs::write(project.join("node_modules/left-pad/index.js"), "").unwrap();
+        fs::write(project.join("src/main.js"), "").unwrap();
+        let long_ago = SystemTime::now() - 400 * DAY;
+        for file in [
+            "package.json",
+            "node_modules/left-pad/index.js",
+            "src/main.js",
+        ] {
+            set_modified(&project.join(file), long_ago);
         }
-        size
+
+        let scan = |index: ScanIndex| {
+            let scanner = Scanner::new(0)
+                .with_activity_signal(ActivitySignal::Sources)
+                .with_index(index);
+            let projects = scanner.scan(&root);
+            (projects, scanner.into_index().unwrap())
+        };
+        let (projects, index) = scan(ScanIndex::default());
+        assert_eq!(projects.len(), 1);
+        assert!(projects[0].days_old() >= 399);
+
+        // Editing a file changes nothing the artifact's fingerprint covers
+        fs::write(project.join("src/main.js"), "edited").unwrap();
+        let (projects, _) = scan(index);
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].days_old(), 0);
+        assert_eq!(projects[0].activity, ActivitySignal::Sources);
+
+        fs::remove_dir_all(&root).unwrap();
     }
 }
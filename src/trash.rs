@@ -0,0 +1,127 @@
+//! Move directories to the freedesktop.org Trash.
+//!
+//! Items on the home filesystem go to `$XDG_DATA_HOME/Trash`. Items on other
+//! filesystems go to `$topdir/.Trash-$uid` on that filesystem, because a
+//! rename cannot cross devices and copying gigabytes into the home trash
+//! would defeat the point of cleaning up.
+//! See <https://specifications.freedesktop.org/trash-spec/trashspec-latest.html>
+
+/// Whether `move_to_trash` is available on this platform
+pub fn is_supported() -> bool {
+    cfg!(target_os = "linux")
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::move_to_trash;
+
+#[cfg(not(target_os = "linux"))]
+pub fn move_to_trash(_path: &std::path::Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "moving to trash is only supported on Linux",
+    ))
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::fs;
+    use std::io::{self, Write};
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    use std::path::{Path, PathBuf};
+
+    pub fn move_to_trash(path: &Path) -> io::Result<()> {
+        let path = path.canonicalize()?;
+        let device = path.symlink_metadata()?.dev();
+
+        let home_trash = dirs::data_dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?
+            .join("Trash");
+        fs::create_dir_all(&home_trash)?;
+
+        if fs::metadata(&home_trash)?.dev() == device {
+            return trash_into(&path, &home_trash, &path);
+        }
+
+        // Different filesystem: use the per-user trash at the top of that mount,
+        // where the original path is recorded relative to the mount
+        let topdir = mount_root(&path, device);
+        let trash = topdir.join(format!(".Trash-{}", unsafe { libc::getuid() }));
+        fs::create_dir_all(&trash)?;
+        fs::set_permissions(&trash, fs::Permissions::from_mode(0o700))?;
+        let relative = path.strip_prefix(&topdir).unwrap_or(&path);
+        trash_into(&path, &trash, relative)
+    }
+
+    /// Move `path` into `trash`, recording `original` in its .trashinfo
+    fn trash_into(path: &Path, trash: &Path, original: &Path) -> io::Result<()> {
+        let files_dir = trash.join("files");
+        let info_dir = trash.join("info");
+        fs::create_dir_all(&files_dir)?;
+        fs::create_dir_all(&info_dir)?;
+
+        let base_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "artifact".to_string());
+        let info = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            percent_encode(original.as_os_str().as_bytes()),
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+        );
+
+        // Claim a free name by atomically creating its .trashinfo, as the spec requires
+        let mut attempt = 1;
+        loop {
+            let name = if attempt == 1 {
+                base_name.clone()
+            } else {
+                format!("{}.{}", base_name, attempt)
+            };
+            let info_file = info_dir.join(format!("{}.trashinfo", name));
+
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_file)
+            {
+                Ok(mut file) => {
+                    file.write_all(info.as_bytes())?;
+                    if let Err(e) = fs::rename(path, files_dir.join(&name)) {
+                        fs::remove_file(&info_file).ok();
+                        return Err(e);
+                    }
+                    return Ok(());
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => attempt += 1,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Walk up from `path` to the highest ancestor still on `device`
+    fn mount_root(path: &Path, device: u64) -> PathBuf {
+        let mut root = path.to_path_buf();
+        for ancestor in path.ancestors().skip(1) {
+            match fs::metadata(ancestor) {
+                Ok(metadata) if metadata.dev() == device => root = ancestor.to_path_buf(),
+                _ => break,
+            }
+        }
+        root
+    }
+
+    /// Escape a path for the `Path=` key, keeping `/` and unreserved characters
+    fn percent_encode(bytes: &[u8]) -> String {
+        let mut encoded = String::with_capacity(bytes.len());
+        for &byte in bytes {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                    encoded.push(byte as char)
+                }
+                _ => encoded.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+        encoded
+    }
+}
@@ -1,17 +1,26 @@
-use crate::app::StorageCleaner;
+use crate::app::{display_root, FilterField, StorageCleaner};
+use crate::cleaner::DeleteMode;
+use crate::config::Config;
+use crate::listing::{self, Group, GroupBy};
+use crate::rules::RuleAction;
 use gpui::prelude::*;
 use gpui::*;
+use std::path::PathBuf;
 
 mod theme;
 use theme::Theme;
 
 pub fn render_app(
     app: &mut StorageCleaner,
-    _window: &mut Window,
+    window: &mut Window,
     cx: &mut Context<StorageCleaner>,
 ) -> impl IntoElement {
     let theme = Theme::coder_black();
     let selected_count = app.selected_count();
+    let editing_pattern = app.pattern_focus.is_focused(window);
+    let editing_filter = app
+        .filter_edit
+        .filter(|_| app.filter_focus.is_focused(window));
     let total_size_gb = app.total_selected_size_gb();
 
     div()
@@ -21,17 +30,44 @@ pub fn render_app(
         .bg(theme.background)
         .text_color(theme.text)
         .font_family("monospace")
-        .child(render_header(app, selected_count, total_size_gb, cx))
-        .child(render_project_list(app, cx))
+        .child(render_header(
+            app,
+            selected_count,
+            total_size_gb,
+            editing_filter,
+            cx,
+        ))
+        .when(app.preview.is_some(), |this| {
+            this.child(render_preview(app, cx))
+        })
+        .when(app.preview.is_none() && app.show_quarantine, |this| {
+            this.child(render_quarantine_list(app, cx))
+        })
+        .when(app.preview.is_none() && app.show_patterns, |this| {
+            this.child(render_patterns(app, editing_pattern, cx))
+        })
+        .when(app.preview.is_none() && app.show_errors, |this| {
+            this.child(render_errors(app))
+        })
+        .when(
+            app.preview.is_none() && !app.show_quarantine && !app.show_patterns && !app.show_errors,
+            |this| this.child(render_project_list(app, cx)),
+        )
 }
 
 fn render_header(
     app: &StorageCleaner,
     selected_count: usize,
     total_size_gb: f64,
+    editing_filter: Option<FilterField>,
     cx: &mut Context<StorageCleaner>,
 ) -> impl IntoElement {
     let theme = Theme::coder_black();
+    let has_projects = !app.projects.is_empty();
+    let busy = app.is_scanning || app.is_deleting;
+    let can_delete = selected_count > 0 && !busy && !app.is_previewing;
+    let can_preview = selected_count > 0 && !app.is_deleting && !app.is_previewing;
+    let can_apply_rules = !app.all_projects.is_empty() && !busy;
 
     div()
         .flex()
@@ -61,67 +97,13 @@ fn render_header(
                         .child("v1.0"),
                 ),
         )
-        .child(
-            div()
-                .flex()
-                .gap_2()
-                .child(
-                    div()
-                        .flex_1()
-                        .flex()
-                        .flex_col()
-                        .gap_1()
-                        .child(
-                            div()
-                                .text_xs()
-                                .text_color(theme.text_dim)
-                                .child("SCAN PATH"),
-                        )
-                        .child(
-                            div()
-                                .text_xs()
-                                .text_color(theme.text_muted)
-                                .child(format!("{}", app.config.scan_path.display())),
-                        ),
-                )
-                .child(
-                    div()
-                        .id("change_dir")
-                        .cursor_pointer()
-                        .px_3()
-                        .py_1()
-                        .text_xs()
-                        .font_weight(FontWeight::BOLD)
-                        .bg(theme.element_bg)
-                        .text_color(theme.text_accent)
-                        .border_1()
-                        .border_color(theme.border)
-                        .child("[CHANGE]")
-                        .hover(|s| s.border_color(theme.border_focused))
-                        .on_click(cx.listener(|view, _event, _window, cx| {
-                            if let Some(path) = native_dialog::FileDialog::new()
-                                .set_location(&view.config.scan_path)
-                                .show_open_single_dir()
-                                .ok()
-                                .flatten()
-                            {
-                                view.set_scan_path(path);
-                                cx.notify();
-                            }
-                        })),
-                ),
-        )
+        .child(render_roots(app, busy, cx))
         .child(
             div()
                 .flex()
                 .items_center()
                 .gap_2()
-                .child(
-                    div()
-                        .text_xs()
-                        .text_color(theme.text_dim)
-                        .child("THRESHOLD"),
-                )
+                .child(div().text_xs().text_color(theme.text_dim).child("FILTERS"))
                 .child(
                     div()
                         .id("threshold_toggle")
@@ -176,49 +158,140 @@ fn render_header(
                                 )
                             }),
                     )
-                    .child(
-                        div()
-                            .px_2()
-                            .py_1()
-                            .bg(theme.element_bg)
-                            .border_1()
-                            .border_color(theme.border)
-                            .text_xs()
-                            .text_color(theme.text_accent)
-                            .child(format!("{:03}", app.config.threshold_days)),
-                    )
+                    .child(render_filter_input(
+                        app,
+                        FilterField::MinAge,
+                        editing_filter,
+                        cx,
+                    ))
                     .child(
                         div()
                             .id("threshold_inc")
-                            .when(app.config.threshold_days < 365, |d| d.cursor_pointer())
+                            .cursor_pointer()
                             .px_3()
                             .py_1()
                             .text_xs()
                             .font_weight(FontWeight::BOLD)
                             .bg(theme.element_bg)
-                            .text_color(if app.config.threshold_days < 365 {
-                                theme.text_accent
-                            } else {
-                                theme.text_dim
-                            })
+                            .text_color(theme.text_accent)
                             .border_1()
-                            .border_color(if app.config.threshold_days < 365 {
-                                theme.border
-                            } else {
-                                theme.border_disabled
-                            })
+                            .border_color(theme.border)
                             .child("[+]")
-                            .when(app.config.threshold_days < 365, |d| {
-                                d.hover(|s| s.border_color(theme.border_focused)).on_click(
-                                    cx.listener(|view, _event, _window, cx| {
-                                        view.increase_threshold();
-                                        cx.notify();
-                                    }),
-                                )
-                            }),
+                            .hover(|s| s.border_color(theme.border_focused))
+                            .on_click(cx.listener(|view, _event, _window, cx| {
+                                view.increase_threshold();
+                                cx.notify();
+                            })),
                     )
-                    .child(div().text_xs().text_color(theme.text_dim).child("DAYS"))
-                }),
+                    .child(div().text_xs().text_color(theme.text_dim).child("OLD"))
+                    .child(div().text_xs().text_color(theme.text_dim).child("MAX AGE"))
+                    .child(render_filter_input(
+                        app,
+                        FilterField::MaxAge,
+                        editing_filter,
+                        cx,
+                    ))
+                    .child(div().text_xs().text_color(theme.text_dim).child("MIN SIZE"))
+                    .child(render_filter_input(
+                        app,
+                        FilterField::MinSize,
+                        editing_filter,
+                        cx,
+                    ))
+                })
+                .child(div().text_xs().text_color(theme.text_dim).child("AGE BY"))
+                .child(
+                    div()
+                        .id("activity_signal")
+                        .when(!busy, |d| d.cursor_pointer())
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .font_weight(FontWeight::BOLD)
+                        .bg(theme.element_bg)
+                        .text_color(if busy {
+                            theme.text_dim
+                        } else {
+                            theme.text_accent
+                        })
+                        .border_1()
+                        .border_color(if busy {
+                            theme.border_disabled
+                        } else {
+                            theme.border
+                        })
+                        .child(format!("[{}]", app.config.activity_signal.label()))
+                        .when(!busy, |d| {
+                            d.hover(|s| s.border_color(theme.border_focused))
+                                .on_click(cx.listener(|view, _event, _window, cx| {
+                                    view.cycle_activity_signal(cx);
+                                    cx.notify();
+                                }))
+                        }),
+                )
+                .child(div().text_xs().text_color(theme.text_dim).child("SORT"))
+                .child(
+                    div()
+                        .id("sort_key")
+                        .cursor_pointer()
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .font_weight(FontWeight::BOLD)
+                        .bg(theme.element_bg)
+                        .text_color(theme.text_accent)
+                        .border_1()
+                        .border_color(theme.border)
+                        .child(format!("[{}]", app.config.sort_key.label()))
+                        .hover(|s| s.border_color(theme.border_focused))
+                        .on_click(cx.listener(|view, _event, _window, cx| {
+                            view.cycle_sort_key();
+                            cx.notify();
+                        })),
+                )
+                .child(
+                    div()
+                        .id("sort_order")
+                        .cursor_pointer()
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .font_weight(FontWeight::BOLD)
+                        .bg(theme.element_bg)
+                        .text_color(theme.text_accent)
+                        .border_1()
+                        .border_color(theme.border)
+                        .child(if app.config.sort_ascending {
+                            "[ASC]"
+                        } else {
+                            "[DESC]"
+                        })
+                        .hover(|s| s.border_color(theme.border_focused))
+                        .on_click(cx.listener(|view, _event, _window, cx| {
+                            view.toggle_sort_order();
+                            cx.notify();
+                        })),
+                )
+                .child(div().text_xs().text_color(theme.text_dim).child("GROUP"))
+                .child(
+                    div()
+                        .id("group_by")
+                        .cursor_pointer()
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .font_weight(FontWeight::BOLD)
+                        .bg(theme.element_bg)
+                        .text_color(theme.text_accent)
+                        .border_1()
+                        .border_color(theme.border)
+                        .child(format!("[{}]", app.config.group_by.label()))
+                        .hover(|s| s.border_color(theme.border_focused))
+                        .on_click(cx.listener(|view, _event, _window, cx| {
+                            view.cycle_group_by();
+                            cx.notify();
+                        })),
+                ),
         )
         .when(app.is_scanning, |this| {
             let theme = Theme::coder_black();
@@ -269,19 +342,19 @@ fn render_header(
                 .child(
                     div()
                         .id("scan_button")
-                        .when(!app.is_scanning, |d| d.cursor_pointer())
+                        .when(!busy, |d| d.cursor_pointer())
                         .px_3()
                         .py_1()
                         .text_xs()
                         .font_weight(FontWeight::BOLD)
                         .bg(theme.element_bg)
-                        .text_color(if app.is_scanning {
+                        .text_color(if busy {
                             theme.text_dim
                         } else {
                             theme.text_accent
                         })
                         .border_1()
-                        .border_color(if app.is_scanning {
+                        .border_color(if busy {
                             theme.border_disabled
                         } else {
                             theme.border
@@ -291,10 +364,119 @@ fn render_header(
                         } else {
                             "[SCAN]"
                         })
-                        .when(!app.is_scanning, |d| {
+                        .when(!busy, |d| {
+                            d.hover(|s| s.border_color(theme.border_focused))
+                                .on_click(cx.listener(|view, _event, _window, cx| {
+                                    view.scan_for_projects(cx);
+                                    cx.notify();
+                                }))
+                        }),
+                )
+                .when(busy, |this| {
+                    this.child(
+                        div()
+                            .id("cancel_button")
+                            .cursor_pointer()
+                            .px_3()
+                            .py_1()
+                            .text_xs()
+                            .font_weight(FontWeight::BOLD)
+                            .bg(theme.element_bg)
+                            .text_color(theme.warning)
+                            .border_1()
+                            .border_color(theme.warning)
+                            .child("[CANCEL]")
+                            .hover(|s| s.border_color(theme.border_focused))
+                            .on_click(cx.listener(|view, _event, _window, cx| {
+                                view.cancel();
+                                cx.notify();
+                            })),
+                    )
+                })
+                .child(
+                    div()
+                        .id("delete_mode")
+                        .cursor_pointer()
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .font_weight(FontWeight::BOLD)
+                        .bg(theme.element_bg)
+                        .text_color(match app.config.delete_mode {
+                            DeleteMode::Trash | DeleteMode::Quarantine => theme.text_accent,
+                            DeleteMode::Permanent => theme.warning,
+                        })
+                        .border_1()
+                        .border_color(theme.border)
+                        .child(match app.config.delete_mode {
+                            DeleteMode::Trash => "[MODE: TRASH]",
+                            DeleteMode::Quarantine => "[MODE: QUARANTINE]",
+                            DeleteMode::Permanent => "[MODE: PERMANENT]",
+                        })
+                        .hover(|s| s.border_color(theme.border_focused))
+                        .on_click(cx.listener(|view, _event, _window, cx| {
+                            view.toggle_delete_mode();
+                            cx.notify();
+                        })),
+                )
+                .child(
+                    div()
+                        .id("rules_button")
+                        .when(can_apply_rules, |d| d.cursor_pointer())
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .font_weight(FontWeight::BOLD)
+                        .bg(theme.element_bg)
+                        .text_color(if can_apply_rules {
+                            theme.text_accent
+                        } else {
+                            theme.text_dim
+                        })
+                        .border_1()
+                        .border_color(if can_apply_rules {
+                            theme.border
+                        } else {
+                            theme.border_disabled
+                        })
+                        .child("[APPLY RULES]")
+                        .when(can_apply_rules, |d| {
+                            d.hover(|s| s.border_color(theme.border_focused))
+                                .on_click(cx.listener(|view, _event, _window, cx| {
+                                    view.apply_rules();
+                                    cx.notify();
+                                }))
+                        }),
+                )
+                .child(
+                    div()
+                        .id("preview_button")
+                        .when(can_preview, |d| d.cursor_pointer())
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .font_weight(FontWeight::BOLD)
+                        .bg(theme.element_bg)
+                        .text_color(if can_preview {
+                            theme.text_accent
+                        } else {
+                            theme.text_dim
+                        })
+                        .border_1()
+                        .border_color(if can_preview {
+                            theme.border
+                        } else {
+                            theme.border_disabled
+                        })
+                        .child(if app.is_previewing {
+                            "[PREVIEWING...]".to_string()
+                        } else {
+                            format!("[PREVIEW] ({})", selected_count)
+                        })
+                        .when(can_preview, |d| {
                             d.hover(|s| s.border_color(theme.border_focused))
                                 .on_click(cx.listener(|view, _event, _window, cx| {
-                                    view.scan_for_projects();
+                                    view.preview_selected(cx);
                                     cx.notify();
                                 }))
                         }),
@@ -302,42 +484,177 @@ fn render_header(
                 .child(
                     div()
                         .id("delete_button")
-                        .when(selected_count > 0, |d| d.cursor_pointer())
+                        .when(can_delete, |d| d.cursor_pointer())
                         .px_3()
                         .py_1()
                         .text_xs()
                         .font_weight(FontWeight::BOLD)
                         .bg(theme.element_bg)
-                        .text_color(if selected_count > 0 {
+                        .text_color(if can_delete {
                             theme.error
                         } else {
                             theme.text_dim
                         })
                         .border_1()
-                        .border_color(if selected_count > 0 {
+                        .border_color(if can_delete {
                             theme.error
                         } else {
                             theme.border_disabled
                         })
-                        .child(format!("[DELETE NODE_MODULES] ({})", selected_count))
-                        .when(selected_count > 0, |d| {
+                        .child(match app.config.delete_mode {
+                            _ if app.is_deleting => "[REMOVING...]".to_string(),
+                            _ if app.confirm_local_work => {
+                                format!("[CONFIRM REMOVAL] ({})", selected_count)
+                            }
+                            DeleteMode::Trash => format!("[MOVE TO TRASH] ({})", selected_count),
+                            DeleteMode::Quarantine => {
+                                format!("[QUARANTINE] ({})", selected_count)
+                            }
+                            DeleteMode::Permanent => {
+                                format!("[DELETE PERMANENTLY] ({})", selected_count)
+                            }
+                        })
+                        .when(can_delete, |d| {
                             d.hover(|s| s.border_color(theme.error))
                                 .on_click(cx.listener(|view, _event, _window, cx| {
-                                    view.delete_selected();
+                                    view.delete_selected(cx);
                                     cx.notify();
                                 }))
                         }),
-                ),
-        )
-        .child(
-            div()
-                .flex()
-                .justify_between()
+                )
                 .child(
                     div()
+                        .id("export_button")
+                        .when(has_projects, |d| d.cursor_pointer())
+                        .px_3()
+                        .py_1()
                         .text_xs()
-                        .text_color(theme.text_muted)
-                        .child(app.status_message.clone()),
+                        .font_weight(FontWeight::BOLD)
+                        .bg(theme.element_bg)
+                        .text_color(if has_projects {
+                            theme.text_accent
+                        } else {
+                            theme.text_dim
+                        })
+                        .border_1()
+                        .border_color(if has_projects {
+                            theme.border
+                        } else {
+                            theme.border_disabled
+                        })
+                        .child("[EXPORT]")
+                        .when(has_projects, |d| {
+                            d.hover(|s| s.border_color(theme.border_focused))
+                                .on_click(cx.listener(|view, _event, _window, cx| {
+                                    if let Some(path) = native_dialog::FileDialog::new()
+                                        .set_location(&dialog_location(&view.config))
+                                        .set_filename("dev-storage-report.json")
+                                        .add_filter("JSON", &["json"])
+                                        .add_filter("CSV", &["csv"])
+                                        .add_filter("NDJSON", &["ndjson"])
+                                        .show_save_single_file()
+                                        .ok()
+                                        .flatten()
+                                    {
+                                        view.export_report(path);
+                                        cx.notify();
+                                    }
+                                }))
+                        }),
+                )
+                .child(
+                    div()
+                        .id("quarantine_button")
+                        .cursor_pointer()
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .font_weight(FontWeight::BOLD)
+                        .bg(if app.show_quarantine {
+                            theme.text_accent
+                        } else {
+                            theme.element_bg
+                        })
+                        .text_color(if app.show_quarantine {
+                            theme.background
+                        } else {
+                            theme.text_accent
+                        })
+                        .border_1()
+                        .border_color(theme.border)
+                        .child(format!("[QUARANTINE: {}]", app.quarantine.len()))
+                        .hover(|s| s.border_color(theme.border_focused))
+                        .on_click(cx.listener(|view, _event, _window, cx| {
+                            view.toggle_quarantine_view();
+                            cx.notify();
+                        })),
+                )
+                .child(
+                    div()
+                        .id("patterns_button")
+                        .cursor_pointer()
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .font_weight(FontWeight::BOLD)
+                        .bg(if app.show_patterns {
+                            theme.text_accent
+                        } else {
+                            theme.element_bg
+                        })
+                        .text_color(if app.show_patterns {
+                            theme.background
+                        } else {
+                            theme.text_accent
+                        })
+                        .border_1()
+                        .border_color(theme.border)
+                        .child(format!("[PATTERNS: {}]", app.config.scan_patterns.len()))
+                        .hover(|s| s.border_color(theme.border_focused))
+                        .on_click(cx.listener(|view, _event, _window, cx| {
+                            view.toggle_patterns_view();
+                            cx.notify();
+                        })),
+                )
+                .when(!app.scan_errors.is_empty(), |this| {
+                    this.child(
+                        div()
+                            .id("errors_button")
+                            .cursor_pointer()
+                            .px_3()
+                            .py_1()
+                            .text_xs()
+                            .font_weight(FontWeight::BOLD)
+                            .bg(if app.show_errors {
+                                theme.warning
+                            } else {
+                                theme.element_bg
+                            })
+                            .text_color(if app.show_errors {
+                                theme.background
+                            } else {
+                                theme.warning
+                            })
+                            .border_1()
+                            .border_color(theme.border)
+                            .child(format!("[ERRORS: {}]", app.scan_errors.len()))
+                            .hover(|s| s.border_color(theme.border_focused))
+                            .on_click(cx.listener(|view, _event, _window, cx| {
+                                view.toggle_errors_view();
+                                cx.notify();
+                            })),
+                    )
+                }),
+        )
+        .child(
+            div()
+                .flex()
+                .justify_between()
+                .child(
+                    div()
+                        .text_xs()
+                        .text_color(theme.text_muted)
+                        .child(app.status_message.clone()),
                 )
                 .when(selected_count > 0, |this| {
                     this.child(
@@ -350,9 +667,212 @@ fn render_header(
         )
 }
 
+/// A limit shown as its value, typed into after a click
+fn render_filter_input(
+    app: &StorageCleaner,
+    field: FilterField,
+    editing: Option<FilterField>,
+    cx: &mut Context<StorageCleaner>,
+) -> impl IntoElement {
+    let theme = Theme::coder_black();
+    let editing = editing == Some(field);
+    let id = match field {
+        FilterField::MinAge => "filter_min_age",
+        FilterField::MaxAge => "filter_max_age",
+        FilterField::MinSize => "filter_min_size",
+    };
+
+    div()
+        .id(id)
+        // One input at a time owns the focus handle
+        .when(app.filter_edit == Some(field), |d| {
+            d.track_focus(&app.filter_focus)
+        })
+        .cursor_pointer()
+        .px_2()
+        .py_1()
+        .min_w(px(48.0))
+        .bg(theme.element_bg)
+        .border_1()
+        .border_color(if editing {
+            theme.border_focused
+        } else {
+            theme.border
+        })
+        .text_xs()
+        .text_color(theme.text_accent)
+        .child(if editing {
+            format!("{}_", app.filter_input)
+        } else {
+            app.filter_label(field)
+        })
+        .on_click(cx.listener(move |view, _event, window, cx| {
+            view.edit_filter(field);
+            window.focus(&view.filter_focus);
+            cx.notify();
+        }))
+        .on_key_down(cx.listener(|view, event: &KeyDownEvent, _window, cx| {
+            view.handle_filter_key(event);
+            cx.notify();
+        }))
+}
+
+fn render_roots(
+    app: &StorageCleaner,
+    busy: bool,
+    cx: &mut Context<StorageCleaner>,
+) -> impl IntoElement {
+    let theme = Theme::coder_black();
+
+    div()
+        .flex()
+        .gap_2()
+        .child(
+            div()
+                .flex_1()
+                .flex()
+                .flex_col()
+                .gap_1()
+                .child(
+                    div()
+                        .text_xs()
+                        .text_color(theme.text_dim)
+                        .child("SCAN ROOTS"),
+                )
+                .when(app.config.roots.is_empty(), |this| {
+                    this.child(
+                        div()
+                            .text_xs()
+                            .text_color(theme.text_dim)
+                            .child("[ NO ROOTS - CLICK ADD ]"),
+                    )
+                })
+                .children(app.config.roots.iter().enumerate().map(|(index, root)| {
+                    div()
+                        .flex()
+                        .items_center()
+                        .gap_2()
+                        .text_xs()
+                        .child(
+                            div()
+                                .id(("root_toggle", index))
+                                .when(!busy, |d| d.cursor_pointer())
+                                .font_weight(FontWeight::BOLD)
+                                .text_color(if root.enabled {
+                                    theme.text_accent
+                                } else {
+                                    theme.text_dim
+                                })
+                                .child(if root.enabled { "[ON]" } else { "[OFF]" })
+                                .when(!busy, |d| {
+                                    d.on_click(cx.listener(move |view, _event, _window, cx| {
+                                        view.toggle_root(index, cx);
+                                        cx.notify();
+                                    }))
+                                }),
+                        )
+                        .child(
+                            div()
+                                .flex_1()
+                                .text_color(if root.enabled {
+                                    theme.text_muted
+                                } else {
+                                    theme.text_dim
+                                })
+                                .child(format!("{}", root.path.display())),
+                        )
+                        .child(
+                            div()
+                                .id(("root_remove", index))
+                                .when(!busy, |d| d.cursor_pointer())
+                                .font_weight(FontWeight::BOLD)
+                                .text_color(theme.text_dim)
+                                .child("[REMOVE]")
+                                .when(!busy, |d| {
+                                    d.hover(|s| s.text_color(theme.text_accent)).on_click(
+                                        cx.listener(move |view, _event, _window, cx| {
+                                            view.remove_root(index, cx);
+                                            cx.notify();
+                                        }),
+                                    )
+                                }),
+                        )
+                })),
+        )
+        .child(
+            div()
+                .id("add_root")
+                .when(!busy, |d| d.cursor_pointer())
+                .px_3()
+                .py_1()
+                .text_xs()
+                .font_weight(FontWeight::BOLD)
+                .bg(theme.element_bg)
+                .text_color(if busy {
+                    theme.text_dim
+                } else {
+                    theme.text_accent
+                })
+                .border_1()
+                .border_color(if busy {
+                    theme.border_disabled
+                } else {
+                    theme.border
+                })
+                .child("[ADD]")
+                .when(!busy, |d| {
+                    d.hover(|s| s.border_color(theme.border_focused))
+                        .on_click(cx.listener(|view, _event, _window, cx| {
+                            if let Some(path) = native_dialog::FileDialog::new()
+                                .set_location(&dialog_location(&view.config))
+                                .show_open_single_dir()
+                                .ok()
+                                .flatten()
+                            {
+                                view.add_root(path, cx);
+                                cx.notify();
+                            }
+                        }))
+                }),
+        )
+}
+
+/// Where file dialogs open: the first root, or the home directory
+fn dialog_location(config: &Config) -> PathBuf {
+    config
+        .roots
+        .first()
+        .map(|root| root.path.clone())
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
 fn render_project_list(app: &StorageCleaner, cx: &mut Context<StorageCleaner>) -> impl IntoElement {
     let theme = Theme::coder_black();
 
+    let grouped = app.config.group_by != GroupBy::None;
+    let roots = app.config.scan_roots();
+    let mut rows: Vec<AnyElement> = Vec::new();
+    for (group_index, group) in listing::groups(&app.projects, app.config.group_by, &roots)
+        .into_iter()
+        .enumerate()
+    {
+        let collapsed = grouped && app.collapsed_groups.contains(&group.path);
+        if grouped {
+            rows.push(render_group_header(&group, collapsed, group_index, cx).into_any_element());
+        }
+        if !collapsed {
+            for index in group.range {
+                let project = &app.projects[index];
+                let root = app.config.root_of(&project.artifact_path);
+                let protected = app.is_protected(project);
+                rows.push(
+                    render_project_card(project, root, protected, index, cx).into_any_element(),
+                );
+            }
+        }
+    }
+
     div()
         .id("project_list")
         .flex()
@@ -361,12 +881,7 @@ fn render_project_list(app: &StorageCleaner, cx: &mut Context<StorageCleaner>) -
         .overflow_y_scroll()
         .p_2()
         .gap_1()
-        .children(
-            app.projects
-                .iter()
-                .enumerate()
-                .map(|(index, project)| render_project_card(project, index, cx)),
-        )
+        .children(rows)
         .when(app.projects.is_empty() && !app.is_scanning, |this| {
             this.child(
                 div()
@@ -376,13 +891,76 @@ fn render_project_list(app: &StorageCleaner, cx: &mut Context<StorageCleaner>) -
                     .flex_1()
                     .text_xs()
                     .text_color(theme.text_dim)
-                    .child("[ NO NODE_MODULES FOUND - CLICK SCAN TO START ]"),
+                    .child("[ NO BUILD ARTIFACTS FOUND - CLICK SCAN TO START ]"),
             )
         })
 }
 
+/// Clicking the header folds the group's cards away or back
+fn render_group_header(
+    group: &Group,
+    collapsed: bool,
+    index: usize,
+    cx: &mut Context<StorageCleaner>,
+) -> impl IntoElement {
+    let theme = Theme::coder_black();
+    let path = group.path.clone();
+    let label = if group.path.as_os_str().is_empty() {
+        "OTHER".to_string()
+    } else {
+        display_root(&group.path)
+    };
+
+    div()
+        .id(("group", index))
+        .flex()
+        .items_center()
+        .justify_between()
+        .px_2()
+        .py_1()
+        .bg(theme.element_bg)
+        .border_1()
+        .border_color(theme.border)
+        .cursor_pointer()
+        .text_xs()
+        .font_weight(FontWeight::BOLD)
+        .hover(|style| style.border_color(theme.border_focused))
+        .on_click(cx.listener(move |view, _event, _window, cx| {
+            view.toggle_group(&path);
+            cx.notify();
+        }))
+        .child(
+            div()
+                .flex()
+                .gap_2()
+                .child(div().text_color(theme.text_accent).child(if collapsed {
+                    "[+]"
+                } else {
+                    "[-]"
+                }))
+                .child(div().text_color(theme.text).child(label)),
+        )
+        .child(
+            div()
+                .flex()
+                .gap_2()
+                .child(
+                    div()
+                        .text_color(theme.text_dim)
+                        .child(format!("[{} ARTIFACT(S)]", group.len())),
+                )
+                .child(
+                    div()
+                        .text_color(theme.text_accent)
+                        .child(format!("[{:.2} GB]", group.size_gb())),
+                ),
+        )
+}
+
 fn render_project_card(
     project: &crate::scanner::ProjectInfo,
+    root: Option<PathBuf>,
+    protected: bool,
     index: usize,
     cx: &mut Context<StorageCleaner>,
 ) -> impl IntoElement {
@@ -394,15 +972,23 @@ fn render_project_card(
         .flex()
         .p_2()
         .gap_2()
-        .bg(theme.surface)
+        .bg(if protected {
+            theme.element_bg
+        } else {
+            theme.surface
+        })
         .border_1()
-        .border_color(if selected {
+        .border_color(if protected {
+            theme.info
+        } else if selected {
             theme.border_focused
         } else {
             theme.border
         })
         .cursor_pointer()
-        .hover(|style| style.border_color(theme.border_focused))
+        .when(!protected, |this| {
+            this.hover(|style| style.border_color(theme.border_focused))
+        })
         .on_click(cx.listener(move |view, _event, _window, cx| {
             view.toggle_project(index);
             cx.notify();
@@ -415,7 +1001,11 @@ fn render_project_card(
                 .w_4()
                 .h_4()
                 .border_1()
-                .border_color(theme.border_focused)
+                .border_color(if protected {
+                    theme.border_disabled
+                } else {
+                    theme.border_focused
+                })
                 .text_xs()
                 .font_weight(FontWeight::BOLD)
                 .when(selected, |this| {
@@ -457,21 +1047,542 @@ fn render_project_card(
                                 .text_xs()
                                 .child(
                                     div()
-                                        .text_color(theme.text_dim)
-                                        .child(format!("[{} DAYS]", project.days_old())),
+                                        .id(("pin", index))
+                                        .cursor_pointer()
+                                        .text_color(if protected {
+                                            theme.info
+                                        } else {
+                                            theme.text_dim
+                                        })
+                                        .child(if protected { "[PINNED]" } else { "[PIN]" })
+                                        .hover(|s| s.text_color(theme.info))
+                                        .on_click(cx.listener(move |view, _event, _window, cx| {
+                                            // The card behind would toggle the selection
+                                            cx.stop_propagation();
+                                            view.toggle_protected(index);
+                                            cx.notify();
+                                        })),
+                                )
+                                .when_some(root, |row, root| {
+                                    row.child(
+                                        div()
+                                            .text_color(theme.text_dim)
+                                            .child(format!("[{}]", display_root(&root))),
+                                    )
+                                })
+                                .when_some(project.rule.as_ref(), |row, rule| {
+                                    row.child(
+                                        div()
+                                            .text_color(match rule.action {
+                                                RuleAction::Select => theme.text_accent,
+                                                RuleAction::Protect => theme.info,
+                                                RuleAction::Ignore => theme.text_dim,
+                                            })
+                                            .child(format!(
+                                                "[{}: {}]",
+                                                rule.action.label(),
+                                                rule.name.to_uppercase()
+                                            )),
+                                    )
+                                })
+                                .child(
+                                    div()
+                                        .text_color(theme.info)
+                                        .child(format!("[{}]", project.kind.label())),
                                 )
+                                .when_some(project.git.as_ref(), |row, git| {
+                                    row.child(
+                                        div()
+                                            .text_color(if git.has_local_work() {
+                                                theme.warning
+                                            } else {
+                                                theme.text_dim
+                                            })
+                                            .child(format!(
+                                                "[GIT: {}]",
+                                                git.summary().to_uppercase()
+                                            )),
+                                    )
+                                })
+                                .child(div().text_color(theme.text_dim).child(format!(
+                                    "[{} DAYS BY {}]",
+                                    project.days_old(),
+                                    project.activity.label()
+                                )))
                                 .child(
                                     div()
                                         .text_color(theme.text_accent)
                                         .child(format!("[{:.2} GB]", project.size_gb())),
-                                ),
+                                )
+                                .when(project.size_bytes != project.disk_bytes, |row| {
+                                    // Hardlinks and sparse files make the two differ
+                                    row.child(div().text_color(theme.text_dim).child(format!(
+                                        "[{:.2} GB APPARENT]",
+                                        project.apparent_gb()
+                                    )))
+                                }),
                         ),
                 )
                 .child(
                     div()
+                        .flex()
+                        .justify_between()
                         .text_xs()
                         .text_color(theme.text_dim)
-                        .child(format!("{}", project.project_path.display())),
+                        .child(format!("{}", project.artifact_path.display()))
+                        .child(format!("via {}", project.marker)),
                 ),
         )
 }
+
+fn render_quarantine_list(
+    app: &StorageCleaner,
+    cx: &mut Context<StorageCleaner>,
+) -> impl IntoElement {
+    let theme = Theme::coder_black();
+
+    div()
+        .id("quarantine_list")
+        .flex()
+        .flex_col()
+        .flex_1()
+        .overflow_y_scroll()
+        .p_2()
+        .gap_1()
+        .child(
+            div()
+                .flex()
+                .items_center()
+                .gap_2()
+                .child(
+                    div()
+                        .text_xs()
+                        .text_color(theme.text_dim)
+                        .child("PURGE AFTER"),
+                )
+                .child(
+                    div()
+                        .id("quarantine_dec")
+                        .cursor_pointer()
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .font_weight(FontWeight::BOLD)
+                        .bg(theme.element_bg)
+                        .text_color(theme.text_accent)
+                        .border_1()
+                        .border_color(theme.border)
+                        .child("[-]")
+                        .hover(|s| s.border_color(theme.border_focused))
+                        .on_click(cx.listener(|view, _event, _window, cx| {
+                            view.decrease_quarantine_days();
+                            cx.notify();
+                        })),
+                )
+                .child(
+                    div()
+                        .px_2()
+                        .py_1()
+                        .bg(theme.element_bg)
+                        .border_1()
+                        .border_color(theme.border)
+                        .text_xs()
+                        .text_color(theme.text_accent)
+                        .child(format!("{:03}", app.config.quarantine_days)),
+                )
+                .child(
+                    div()
+                        .id("quarantine_inc")
+                        .cursor_pointer()
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .font_weight(FontWeight::BOLD)
+                        .bg(theme.element_bg)
+                        .text_color(theme.text_accent)
+                        .border_1()
+                        .border_color(theme.border)
+                        .child("[+]")
+                        .hover(|s| s.border_color(theme.border_focused))
+                        .on_click(cx.listener(|view, _event, _window, cx| {
+                            view.increase_quarantine_days();
+                            cx.notify();
+                        })),
+                )
+                .child(div().text_xs().text_color(theme.text_dim).child("DAYS")),
+        )
+        .children(app.quarantine.iter().enumerate().map(|(index, entry)| {
+            let id = entry.id.clone();
+            let days_left =
+                (app.config.quarantine_days as u64).saturating_sub(entry.days_in_quarantine());
+
+            div()
+                .id(("quarantined", index))
+                .flex()
+                .items_center()
+                .p_2()
+                .gap_2()
+                .bg(theme.surface)
+                .border_1()
+                .border_color(theme.border)
+                .child(
+                    div()
+                        .flex()
+                        .flex_col()
+                        .gap_1()
+                        .flex_1()
+                        .child(
+                            div()
+                                .text_xs()
+                                .text_color(theme.text)
+                                .child(format!("{}", entry.original_path.display())),
+                        )
+                        .child(
+                            div()
+                                .flex()
+                                .gap_2()
+                                .text_xs()
+                                .child(
+                                    div()
+                                        .text_color(theme.text_dim)
+                                        .child(format!("[PURGED IN {} DAYS]", days_left)),
+                                )
+                                .child(
+                                    div()
+                                        .text_color(theme.text_accent)
+                                        .child(format!("[{:.2} GB]", entry.size_gb())),
+                                ),
+                        ),
+                )
+                .child(
+                    div()
+                        .id(("restore", index))
+                        .cursor_pointer()
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .font_weight(FontWeight::BOLD)
+                        .bg(theme.element_bg)
+                        .text_color(theme.text_accent)
+                        .border_1()
+                        .border_color(theme.border)
+                        .child("[RESTORE]")
+                        .hover(|s| s.border_color(theme.border_focused))
+                        .on_click(cx.listener(move |view, _event, _window, cx| {
+                            view.restore_quarantined(&id);
+                            cx.notify();
+                        })),
+                )
+        }))
+        .when(app.quarantine.is_empty(), |this| {
+            this.child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_center()
+                    .flex_1()
+                    .text_xs()
+                    .text_color(theme.text_dim)
+                    .child("[ QUARANTINE IS EMPTY ]"),
+            )
+        })
+}
+
+fn render_patterns(
+    app: &StorageCleaner,
+    editing: bool,
+    cx: &mut Context<StorageCleaner>,
+) -> impl IntoElement {
+    let theme = Theme::coder_black();
+
+    div()
+        .id("pattern_list")
+        .flex()
+        .flex_col()
+        .flex_1()
+        .overflow_y_scroll()
+        .p_2()
+        .gap_1()
+        .child(div().text_xs().text_color(theme.text_dim).child(
+            "SKIP DIRECTORIES MATCHING - `!` RE-INCLUDES, `/` ANCHORS TO THE ROOT, LAST MATCH WINS",
+        ))
+        .child(
+            div()
+                .flex()
+                .items_center()
+                .gap_2()
+                .child(
+                    div()
+                        .id("pattern_input")
+                        .track_focus(&app.pattern_focus)
+                        .flex_1()
+                        .px_2()
+                        .py_1()
+                        .text_xs()
+                        .bg(theme.element_bg)
+                        .border_1()
+                        .border_color(if editing {
+                            theme.border_focused
+                        } else {
+                            theme.border
+                        })
+                        .text_color(if app.pattern_input.is_empty() && !editing {
+                            theme.text_dim
+                        } else {
+                            theme.text
+                        })
+                        .child(match (app.pattern_input.is_empty(), editing) {
+                            (true, false) => {
+                                "CLICK TO TYPE A PATTERN, E.G. vendor/ OR !.config".to_string()
+                            }
+                            (_, true) => format!("{}_", app.pattern_input),
+                            (false, false) => app.pattern_input.clone(),
+                        })
+                        .on_click(cx.listener(|view, _event, window, cx| {
+                            window.focus(&view.pattern_focus);
+                            cx.notify();
+                        }))
+                        .on_key_down(cx.listener(|view, event: &KeyDownEvent, _window, cx| {
+                            view.handle_pattern_key(event);
+                            cx.notify();
+                        })),
+                )
+                .child(
+                    div()
+                        .id("pattern_add")
+                        .cursor_pointer()
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .font_weight(FontWeight::BOLD)
+                        .bg(theme.element_bg)
+                        .text_color(theme.text_accent)
+                        .border_1()
+                        .border_color(theme.border)
+                        .child("[ADD]")
+                        .hover(|s| s.border_color(theme.border_focused))
+                        .on_click(cx.listener(|view, _event, _window, cx| {
+                            view.add_pattern();
+                            cx.notify();
+                        })),
+                )
+                .child(
+                    div()
+                        .id("pattern_reset")
+                        .cursor_pointer()
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .font_weight(FontWeight::BOLD)
+                        .bg(theme.element_bg)
+                        .text_color(theme.text_accent)
+                        .border_1()
+                        .border_color(theme.border)
+                        .child("[DEFAULTS]")
+                        .hover(|s| s.border_color(theme.border_focused))
+                        .on_click(cx.listener(|view, _event, _window, cx| {
+                            view.reset_patterns();
+                            cx.notify();
+                        })),
+                ),
+        )
+        .children(
+            app.config
+                .scan_patterns
+                .iter()
+                .enumerate()
+                .map(|(index, pattern)| {
+                    div()
+                        .flex()
+                        .items_center()
+                        .p_2()
+                        .gap_2()
+                        .bg(theme.surface)
+                        .border_1()
+                        .border_color(theme.border)
+                        .text_xs()
+                        .child(
+                            div()
+                                .flex_1()
+                                .text_color(if pattern.starts_with('!') {
+                                    theme.info
+                                } else {
+                                    theme.text
+                                })
+                                .child(pattern.clone()),
+                        )
+                        .child(
+                            div()
+                                .id(("pattern_remove", index))
+                                .cursor_pointer()
+                                .font_weight(FontWeight::BOLD)
+                                .text_color(theme.text_dim)
+                                .child("[REMOVE]")
+                                .hover(|s| s.text_color(theme.text_accent))
+                                .on_click(cx.listener(move |view, _event, _window, cx| {
+                                    view.remove_pattern(index);
+                                    cx.notify();
+                                })),
+                        )
+                }),
+        )
+        .when(app.config.scan_patterns.is_empty(), |this| {
+            this.child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_center()
+                    .flex_1()
+                    .text_xs()
+                    .text_color(theme.text_dim)
+                    .child("[ NO PATTERNS - EVERY DIRECTORY IS SCANNED ]"),
+            )
+        })
+}
+
+fn render_errors(app: &StorageCleaner) -> impl IntoElement {
+    let theme = Theme::coder_black();
+
+    div()
+        .id("error_list")
+        .flex()
+        .flex_col()
+        .flex_1()
+        .overflow_y_scroll()
+        .p_2()
+        .gap_1()
+        .child(
+            div()
+                .text_xs()
+                .text_color(theme.text_dim)
+                .child("ARTIFACTS BELOW THESE PATHS ARE MISSING AND SIZES MAY BE TOO SMALL"),
+        )
+        .children(app.scan_errors.iter().enumerate().map(|(index, error)| {
+            div()
+                .id(("scan_error", index))
+                .flex()
+                .flex_col()
+                .gap_1()
+                .p_2()
+                .bg(theme.surface)
+                .border_1()
+                .border_color(theme.border)
+                .child(
+                    div()
+                        .flex()
+                        .gap_2()
+                        .text_xs()
+                        .child(
+                            div()
+                                .text_color(theme.warning)
+                                .child(format!("[{}]", error.kind.label())),
+                        )
+                        .child(
+                            div()
+                                .text_color(theme.text)
+                                .child(format!("{}", error.path.display())),
+                        ),
+                )
+                .child(
+                    div()
+                        .text_xs()
+                        .text_color(theme.text_muted)
+                        .child(error.message.clone()),
+                )
+        }))
+}
+
+fn render_preview(app: &StorageCleaner, cx: &mut Context<StorageCleaner>) -> impl IntoElement {
+    let theme = Theme::coder_black();
+    let artifacts = app
+        .preview
+        .as_ref()
+        .map(|report| report.artifacts.as_slice())
+        .unwrap_or_default();
+
+    div()
+        .id("preview")
+        .flex()
+        .flex_col()
+        .flex_1()
+        .overflow_y_scroll()
+        .p_2()
+        .gap_1()
+        .child(
+            div()
+                .flex()
+                .items_center()
+                .justify_between()
+                .child(
+                    div()
+                        .text_xs()
+                        .text_color(theme.text_dim)
+                        .child("DRY RUN - NOTHING HAS BEEN REMOVED"),
+                )
+                .child(
+                    div()
+                        .id("close_preview")
+                        .cursor_pointer()
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .font_weight(FontWeight::BOLD)
+                        .bg(theme.element_bg)
+                        .text_color(theme.text_accent)
+                        .border_1()
+                        .border_color(theme.border)
+                        .child("[CLOSE]")
+                        .hover(|s| s.border_color(theme.border_focused))
+                        .on_click(cx.listener(|view, _event, _window, cx| {
+                            view.close_preview();
+                            cx.notify();
+                        })),
+                ),
+        )
+        .children(artifacts.iter().map(|artifact| {
+            div()
+                .flex()
+                .flex_col()
+                .p_2()
+                .gap_1()
+                .bg(theme.surface)
+                .border_1()
+                .border_color(
+                    if artifact.findings.is_empty() && artifact.omitted_findings == 0 {
+                        theme.border
+                    } else {
+                        theme.warning
+                    },
+                )
+                .child(
+                    div()
+                        .flex()
+                        .justify_between()
+                        .text_xs()
+                        .child(
+                            div()
+                                .text_color(theme.text)
+                                .child(format!("{}", artifact.artifact_path.display())),
+                        )
+                        .child(div().text_color(theme.text_accent).child(format!(
+                            "[{} FILES] [{:.2} GB]",
+                            artifact.file_count,
+                            artifact.total_bytes as f64 / (1024.0 * 1024.0 * 1024.0)
+                        ))),
+                )
+                .children(artifact.findings.iter().map(|finding| {
+                    div()
+                        .text_xs()
+                        .text_color(theme.warning)
+                        .child(format!("! {}", finding))
+                }))
+                .when(artifact.omitted_findings > 0, |this| {
+                    this.child(
+                        div()
+                            .text_xs()
+                            .text_color(theme.warning)
+                            .child(format!("! ... and {} more", artifact.omitted_findings)),
+                    )
+                })
+        }))
+}
@@ -0,0 +1,152 @@
+//! Disk usage of a directory tree.
+//!
+//! Apparent size (`metadata.len()`) overstates what deleting a tree frees:
+//! sparse and small files allocate a different number of blocks, and package
+//! managers like pnpm hardlink one copy of a file into many `node_modules`.
+//! `measure` counts every inode once and only credits a hardlinked file as
+//! freed when all of its links live inside the measured tree.
+
+use crate::cancel::CancelToken;
+use crate::errors::{ErrorLog, ScanError};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiskUsage {
+    /// Sum of file lengths, each inode counted once
+    pub apparent_bytes: u64,
+    /// Allocated bytes that deleting the tree would actually free
+    pub disk_bytes: u64,
+}
+
+impl std::ops::Add for DiskUsage {
+    type Output = DiskUsage;
+
+    fn add(self, other: DiskUsage) -> DiskUsage {
+        DiskUsage {
+            apparent_bytes: self.apparent_bytes + other.apparent_bytes,
+            disk_bytes: self.disk_bytes + other.disk_bytes,
+        }
+    }
+}
+
+impl std::iter::Sum for DiskUsage {
+    fn sum<I: Iterator<Item = DiskUsage>>(iter: I) -> DiskUsage {
+        iter.fold(DiskUsage::default(), |a, b| a + b)
+    }
+}
+
+/// A file with more than one hardlink, seen `seen` times so far
+struct Linked {
+    usage: DiskUsage,
+    links: u64,
+    seen: u64,
+}
+
+/// Measure `path`, walking subdirectories in parallel. Stops early and
+/// returns a partial result when `cancel` is triggered.
+///
+/// Also returns what could not be read; the usage is too small if there is any.
+pub fn measure(path: &Path, cancel: &CancelToken) -> (DiskUsage, Vec<ScanError>) {
+    let linked = Mutex::new(HashMap::new());
+    let errors = ErrorLog::default();
+    let unique = measure_dir(path, cancel, &linked, &errors);
+
+    let linked = linked.into_inner().unwrap_or_default();
+    let shared: DiskUsage = linked
+        .into_values()
+        .map(|file: Linked| DiskUsage {
+            apparent_bytes: file.usage.apparent_bytes,
+            // Links elsewhere keep the data alive, so nothing is freed
+            disk_bytes: if file.seen >= file.links {
+                file.usage.disk_bytes
+            } else {
+                0
+            },
+        })
+        .sum();
+
+    (unique + shared, errors.into_errors())
+}
+
+fn measure_dir(
+    path: &Path,
+    cancel: &CancelToken,
+    linked: &Mutex<HashMap<(u64, u64), Linked>>,
+    errors: &ErrorLog,
+) -> DiskUsage {
+    if cancel.is_cancelled() {
+        return DiskUsage::default();
+    }
+
+    let mut usage = DiskUsage::default();
+    let mut subdirs = Vec::new();
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            errors.record(path, "cannot read directory", &e);
+            return usage;
+        }
+    };
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                errors.record(path, "cannot read directory entry", &e);
+                continue;
+            }
+        };
+        // DirEntry::metadata does not follow symlinks
+        match entry.metadata() {
+            Ok(metadata) if metadata.is_file() => usage = usage + file_usage(&metadata, linked),
+            Ok(metadata) if metadata.is_dir() => subdirs.push(entry.path()),
+            Ok(_) => {}
+            Err(e) => errors.record(&entry.path(), "cannot read metadata", &e),
+        }
+    }
+
+    usage
+        + subdirs
+            .par_iter()
+            .map(|subdir| measure_dir(subdir, cancel, linked, errors))
+            .sum::<DiskUsage>()
+}
+
+/// Usage of a single file. Hardlinked files are parked in `linked` and
+/// counted once at the end instead.
+#[cfg(unix)]
+fn file_usage(metadata: &fs::Metadata, linked: &Mutex<HashMap<(u64, u64), Linked>>) -> DiskUsage {
+    use std::os::unix::fs::MetadataExt;
+
+    // st_blocks is always in 512-byte units, whatever the filesystem block size
+    let usage = DiskUsage {
+        apparent_bytes: metadata.len(),
+        disk_bytes: metadata.blocks() * 512,
+    };
+    if metadata.nlink() <= 1 {
+        return usage;
+    }
+
+    if let Ok(mut linked) = linked.lock() {
+        linked
+            .entry((metadata.dev(), metadata.ino()))
+            .or_insert(Linked {
+                usage,
+                links: metadata.nlink(),
+                seen: 0,
+            })
+            .seen += 1;
+    }
+    DiskUsage::default()
+}
+
+#[cfg(not(unix))]
+fn file_usage(metadata: &fs::Metadata, _linked: &Mutex<HashMap<(u64, u64), Linked>>) -> DiskUsage {
+    DiskUsage {
+        apparent_bytes: metadata.len(),
+        disk_bytes: metadata.len(),
+    }
+}
@@ -0,0 +1,501 @@
+//! Live updates for scan results.
+//!
+//! After a scan the tree is watched with inotify: every directory the scanner
+//! walks, plus the top of every artifact. Events are coalesced per path and
+//! only reported once they settle, so an `npm install` touching thousands of
+//! entries becomes a single re-size of its `node_modules`.
+
+use crate::scanner::{ProjectInfo, Scanner, WalkPosition};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// A directory was created or moved in
+    Appeared(PathBuf),
+    /// A directory was deleted or moved away
+    Vanished(PathBuf),
+    /// Entries directly inside a directory were added, removed or renamed
+    Changed(PathBuf),
+    /// The kernel queue overflowed and events were lost, only a rescan can catch up
+    Overflowed,
+}
+
+/// A change to the scan results worked out from watch events
+#[derive(Clone, Debug)]
+pub enum Update {
+    /// A new or re-sized artifact
+    Found(Box<ProjectInfo>),
+    /// Drop every artifact at or under this path
+    Removed(PathBuf),
+}
+
+/// Whether `Watcher` is available on this platform
+pub fn is_supported() -> bool {
+    cfg!(target_os = "linux")
+}
+
+/// Turn settled `events` into updates for results whose artifact paths are
+/// `known`, keeping the watches in step with what is now on disk. `roots` are
+/// the scan paths, which the depth and filesystem limits count from.
+///
+/// Sizes artifacts as needed, so call it off the UI thread.
+pub fn resolve(
+    events: Vec<WatchEvent>,
+    scanner: &Scanner,
+    watcher: &Watcher,
+    known: &HashSet<PathBuf>,
+    roots: &[PathBuf],
+) -> Vec<Update> {
+    let mut updates = Vec::new();
+
+    for event in events {
+        match event {
+            WatchEvent::Appeared(path) => {
+                let Some(position) = position(&path, roots, scanner) else {
+                    continue;
+                };
+                if !scanner.should_enter(&path, position) {
+                    continue;
+                }
+                updates.push(Update::Removed(path.clone()));
+                updates.extend(
+                    scanner
+                        .scan_at(&path, position)
+                        .into_iter()
+                        .map(|project| Update::Found(Box::new(project))),
+                );
+                watcher.watch_tree(&path, position, scanner).ok();
+            }
+            WatchEvent::Vanished(path) => updates.push(Update::Removed(path)),
+            WatchEvent::Changed(path) if known.contains(&path) => {
+                let Some(position) = position(&path, roots, scanner) else {
+                    continue;
+                };
+                match scanner.scan_artifact(&path, position) {
+                    Some(project) => updates.push(Update::Found(Box::new(project))),
+                    // Its marker went away, so it is an ordinary directory now
+                    None => {
+                        updates.push(Update::Removed(path.clone()));
+                        rewatch(&path, position, scanner, watcher);
+                    }
+                }
+            }
+            WatchEvent::Changed(path) => {
+                // A marker file may have come or gone, which decides whether the
+                // subdirectories next to it are artifacts
+                let Ok(entries) = fs::read_dir(&path) else {
+                    continue;
+                };
+                for entry in entries.filter_map(|e| e.ok()) {
+                    if !entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                        continue;
+                    }
+                    let child = entry.path();
+                    let Some(position) = position(&child, roots, scanner)
+                        .filter(|&position| scanner.should_enter(&child, position))
+                    else {
+                        continue;
+                    };
+                    match (known.contains(&child), scanner.is_artifact(&child)) {
+                        (false, true) => {
+                            rewatch(&child, position, scanner, watcher);
+                            updates.extend(
+                                scanner
+                                    .scan_artifact(&child, position)
+                                    .map(|project| Update::Found(Box::new(project))),
+                            );
+                        }
+                        (true, false) => {
+                            updates.push(Update::Removed(child.clone()));
+                            rewatch(&child, position, scanner, watcher);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            WatchEvent::Overflowed => {}
+        }
+    }
+
+    updates
+}
+
+/// Watch `path` again after it became, or stopped being, an artifact
+fn rewatch(path: &Path, position: WalkPosition, scanner: &Scanner, watcher: &Watcher) {
+    watcher.unwatch_tree(path);
+    watcher.watch_tree(path, position, scanner).ok();
+}
+
+/// The position of `path` in the walk of the innermost scan path holding it
+fn position(path: &Path, roots: &[PathBuf], scanner: &Scanner) -> Option<WalkPosition> {
+    let root = roots
+        .iter()
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count())?;
+    scanner.position(root, path)
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::Watcher;
+
+#[cfg(not(target_os = "linux"))]
+#[derive(Clone)]
+pub struct Watcher;
+
+#[cfg(not(target_os = "linux"))]
+impl Watcher {
+    pub fn new() -> std::io::Result<Self> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "watching for changes is only supported on Linux",
+        ))
+    }
+
+    pub fn watch_tree(
+        &self,
+        _root: &Path,
+        _position: WalkPosition,
+        _scanner: &Scanner,
+    ) -> std::io::Result<usize> {
+        Ok(0)
+    }
+
+    pub fn unwatch_tree(&self, _root: &Path) {}
+
+    pub fn poll(&self) -> Vec<WatchEvent> {
+        Vec::new()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::WatchEvent;
+    use crate::ignore::IgnoreStack;
+    use crate::scanner::{DirId, Scanner, WalkPosition};
+    use std::collections::{HashMap, HashSet};
+    use std::ffi::{CString, OsStr};
+    use std::fs;
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    /// How long a path has to stay quiet before its events are reported
+    const SETTLE: Duration = Duration::from_secs(2);
+
+    /// Ordinary directories: only entries coming and going matter
+    const DIR_MASK: u32 = libc::IN_CREATE
+        | libc::IN_DELETE
+        | libc::IN_MOVED_FROM
+        | libc::IN_MOVED_TO
+        | libc::IN_DELETE_SELF
+        | libc::IN_MOVE_SELF
+        | libc::IN_ONLYDIR
+        | libc::IN_DONT_FOLLOW
+        | libc::IN_EXCL_UNLINK;
+
+    /// Artifacts are watched at the top only. Installs and builds add, remove
+    /// or replace entries there, which is the cue to re-size the whole tree.
+    const ARTIFACT_MASK: u32 = DIR_MASK | libc::IN_ATTRIB;
+
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Pending {
+        Appeared,
+        Vanished,
+        Changed,
+    }
+
+    struct Watched {
+        path: PathBuf,
+        artifact: bool,
+        /// Set when the scanner follows symlinks
+        id: Option<DirId>,
+    }
+
+    #[derive(Default)]
+    struct State {
+        watches: HashMap<i32, Watched>,
+        /// Directories already watched, so links leading back to them are skipped
+        linked: HashSet<DirId>,
+        pending: HashMap<PathBuf, (Pending, Instant)>,
+        overflowed: bool,
+    }
+
+    /// An inotify instance. Clones share it, and it is closed with the last one.
+    #[derive(Clone)]
+    pub struct Watcher {
+        fd: Arc<OwnedFd>,
+        state: Arc<Mutex<State>>,
+    }
+
+    impl Watcher {
+        pub fn new() -> io::Result<Self> {
+            let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self {
+                fd: Arc::new(unsafe { OwnedFd::from_raw_fd(fd) }),
+                state: Arc::new(Mutex::new(State::default())),
+            })
+        }
+
+        /// Watch `root`, found at `position`, and every directory below it
+        /// that `scanner` would enter, stopping at artifacts. Returns the
+        /// number of watches added.
+        ///
+        /// Fails once the per-user limit in `fs.inotify.max_user_watches` is hit.
+        pub fn watch_tree(
+            &self,
+            root: &Path,
+            position: WalkPosition,
+            scanner: &Scanner,
+        ) -> io::Result<usize> {
+            match scanner.ignores(root) {
+                Some(ignores) => self.watch_below(root, &ignores, position, scanner),
+                None => Ok(0),
+            }
+        }
+
+        fn watch_below(
+            &self,
+            root: &Path,
+            ignores: &IgnoreStack,
+            position: WalkPosition,
+            scanner: &Scanner,
+        ) -> io::Result<usize> {
+            // Like the scanner, a directory reached again through a link is
+            // already covered, and walking it again would loop on cycles
+            let id = scanner.link_id(root);
+            if let Some(id) = &id {
+                let first = self
+                    .state
+                    .lock()
+                    .map(|mut state| state.linked.insert(*id))
+                    .unwrap_or(false);
+                if !first {
+                    return Ok(0);
+                }
+            }
+
+            let artifact = scanner.is_artifact(root);
+            let added = self.add(root, artifact, id);
+            if let (Err(_), Some(id), Ok(mut state)) = (&added, &id, self.state.lock()) {
+                state.linked.remove(id);
+            }
+            match added {
+                Err(e) if e.raw_os_error() == Some(libc::ENOSPC) => return Err(e),
+                // Already gone again or unreadable, so there is nothing to watch
+                Err(_) => return Ok(0),
+                Ok(()) if artifact => return Ok(1),
+                Ok(()) => {}
+            }
+
+            let mut added = 1;
+            if !scanner.reads_below(position) {
+                return Ok(added);
+            }
+            let Ok(entries) = fs::read_dir(root) else {
+                return Ok(added);
+            };
+            for entry in entries.filter_map(|e| e.ok()) {
+                if !scanner.is_subdir(&entry) {
+                    continue;
+                }
+                let path = entry.path();
+                let position = position.child();
+                if let Some(ignores) = scanner.enter_at(&path, ignores, position) {
+                    added += self.watch_below(&path, &ignores, position, scanner)?;
+                }
+            }
+            Ok(added)
+        }
+
+        /// Stop watching `root` and everything below it
+        pub fn unwatch_tree(&self, root: &Path) {
+            let Ok(mut state) = self.state.lock() else {
+                return;
+            };
+            let State {
+                watches, linked, ..
+            } = &mut *state;
+            watches.retain(|&wd, watched| {
+                if !watched.path.starts_with(root) {
+                    return true;
+                }
+                if let Some(id) = &watched.id {
+                    linked.remove(id);
+                }
+                // Fails harmlessly when the kernel already dropped the watch
+                unsafe { libc::inotify_rm_watch(self.fd.as_raw_fd(), wd) };
+                false
+            });
+        }
+
+        /// Read whatever the kernel has queued without blocking, and return
+        /// the events that have settled since the last call
+        pub fn poll(&self) -> Vec<WatchEvent> {
+            self.drain();
+
+            let Ok(mut state) = self.state.lock() else {
+                return Vec::new();
+            };
+            if state.overflowed {
+                state.overflowed = false;
+                state.pending.clear();
+                return vec![WatchEvent::Overflowed];
+            }
+
+            let now = Instant::now();
+            let settled: Vec<PathBuf> = state
+                .pending
+                .iter()
+                .filter(|(_, (_, at))| now.duration_since(*at) >= SETTLE)
+                .map(|(path, _)| path.clone())
+                .collect();
+            settled
+                .into_iter()
+                .filter_map(|path| {
+                    let (pending, _) = state.pending.remove(&path)?;
+                    Some(match pending {
+                        Pending::Appeared => WatchEvent::Appeared(path),
+                        Pending::Vanished => WatchEvent::Vanished(path),
+                        Pending::Changed => WatchEvent::Changed(path),
+                    })
+                })
+                .collect()
+        }
+
+        fn add(&self, path: &Path, artifact: bool, id: Option<DirId>) -> io::Result<()> {
+            let c_path = CString::new(path.as_os_str().as_bytes())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            let mask = if artifact { ARTIFACT_MASK } else { DIR_MASK };
+            // A directory reached through a link is only walked when links are followed
+            let mask = if id.is_some() {
+                mask & !libc::IN_DONT_FOLLOW
+            } else {
+                mask
+            };
+            let wd = unsafe { libc::inotify_add_watch(self.fd.as_raw_fd(), c_path.as_ptr(), mask) };
+            if wd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            if let Ok(mut state) = self.state.lock() {
+                state.watches.insert(
+                    wd,
+                    Watched {
+                        path: path.to_path_buf(),
+                        artifact,
+                        id,
+                    },
+                );
+            }
+            Ok(())
+        }
+
+        fn drain(&self) {
+            // Large enough for hundreds of events; aligned for inotify_event
+            let mut buffer = vec![0u64; 8192];
+            let header = std::mem::size_of::<libc::inotify_event>();
+
+            loop {
+                let read = unsafe {
+                    libc::read(
+                        self.fd.as_raw_fd(),
+                        buffer.as_mut_ptr().cast(),
+                        buffer.len() * std::mem::size_of::<u64>(),
+                    )
+                };
+                // EAGAIN once the queue is empty, and no point retrying other errors
+                if read <= 0 {
+                    return;
+                }
+
+                let bytes: &[u8] =
+                    unsafe { std::slice::from_raw_parts(buffer.as_ptr().cast(), read as usize) };
+                let mut offset = 0;
+                while offset + header <= bytes.len() {
+                    let event: libc::inotify_event =
+                        unsafe { std::ptr::read_unaligned(bytes[offset..].as_ptr().cast()) };
+                    let name_bytes = &bytes[offset + header..offset + header + event.len as usize];
+                    let name_len = name_bytes
+                        .iter()
+                        .position(|&b| b == 0)
+                        .unwrap_or(name_bytes.len());
+                    self.handle(
+                        event.wd,
+                        event.mask,
+                        OsStr::from_bytes(&name_bytes[..name_len]),
+                    );
+                    offset += header + event.len as usize;
+                }
+            }
+        }
+
+        fn handle(&self, wd: i32, mask: u32, name: &OsStr) {
+            let Ok(mut state) = self.state.lock() else {
+                return;
+            };
+            if mask & libc::IN_Q_OVERFLOW != 0 {
+                state.overflowed = true;
+                return;
+            }
+            if mask & libc::IN_IGNORED != 0 {
+                state.watches.remove(&wd);
+                return;
+            }
+            let Some(watched) = state.watches.get(&wd) else {
+                return;
+            };
+
+            let (path, pending) = if mask & (libc::IN_DELETE_SELF | libc::IN_MOVE_SELF) != 0 {
+                (watched.path.clone(), Pending::Vanished)
+            } else if watched.artifact {
+                (watched.path.clone(), Pending::Changed)
+            } else if mask & libc::IN_ISDIR != 0 {
+                let child = watched.path.join(name);
+                if mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0 {
+                    (child, Pending::Appeared)
+                } else {
+                    (child, Pending::Vanished)
+                }
+            } else {
+                (watched.path.clone(), Pending::Changed)
+            };
+
+            // A directory that moved away keeps its watches, which would now
+            // report events under its old path
+            if pending == Pending::Vanished {
+                let stale: Vec<i32> = state
+                    .watches
+                    .iter()
+                    .filter(|(_, w)| w.path.starts_with(&path))
+                    .map(|(&wd, _)| wd)
+                    .collect();
+                for wd in stale {
+                    state.watches.remove(&wd);
+                    unsafe { libc::inotify_rm_watch(self.fd.as_raw_fd(), wd) };
+                }
+            }
+
+            let now = Instant::now();
+            state
+                .pending
+                .entry(path)
+                .and_modify(|(existing, at)| {
+                    // Coming and going override each other, but a change to a
+                    // directory that just appeared or vanished adds nothing
+                    if pending != Pending::Changed {
+                        *existing = pending;
+                    }
+                    *at = now;
+                })
+                .or_insert((pending, now));
+        }
+    }
+}